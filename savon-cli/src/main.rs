@@ -0,0 +1,188 @@
+//! `savon` command: generates Rust code from WSDL files, and inspects them.
+
+use clap::{Parser, Subcommand};
+use savon::{
+    gen::{Builder, GenError},
+    wsdl::{self, Wsdl},
+};
+use std::{fs, path::PathBuf, process};
+
+#[derive(Parser)]
+#[command(name = "savon", version, about = "SOAP client generator")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Generate Rust code
+    Generate {
+        wsdl: PathBuf,
+        /// Output file, standard output if not set
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        /// Wrap the generated code in a module
+        #[arg(long)]
+        module: Option<String>,
+        /// Additional derive for generated types, can be repeated
+        #[arg(long)]
+        derive: Vec<String>,
+        /// Visibility of generated items
+        #[arg(long, default_value = "pub")]
+        visibility: String,
+        /// Only generate types and messages
+        #[arg(long)]
+        no_client: bool,
+        /// Also generate a blocking client, in a `blocking` module
+        #[arg(long)]
+        blocking: bool,
+        /// Do not emit doc comments
+        #[arg(long)]
+        no_docs: bool,
+        /// Map xs:float and xs:double to exact decimals
+        #[arg(long)]
+        float_as_decimal: bool,
+    },
+    /// List operations
+    Operations { wsdl: PathBuf },
+    /// List types
+    Types { wsdl: PathBuf },
+    /// List service ports
+    Ports { wsdl: PathBuf },
+    /// Print the parsed model as JSON
+    Dump { wsdl: PathBuf },
+    /// Check that code can be generated and parses, without writing it
+    Validate { wsdl: PathBuf },
+}
+
+#[derive(Debug)]
+enum Error {
+    Io(PathBuf, std::io::Error),
+    Gen(GenError),
+    Json(serde_json::Error),
+    /// Generated code is not valid Rust.
+    Syntax(syn::Error),
+}
+
+impl From<GenError> for Error {
+    fn from(e: GenError) -> Self {
+        Error::Gen(e)
+    }
+}
+
+impl From<wsdl::WsdlError> for Error {
+    fn from(e: wsdl::WsdlError) -> Self {
+        Error::Gen(e.into())
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(e: serde_json::Error) -> Self {
+        Error::Json(e)
+    }
+}
+
+fn parse(path: &PathBuf) -> Result<Wsdl, Error> {
+    let bytes = fs::read(path).map_err(|e| Error::Io(path.clone(), e))?;
+    Ok(wsdl::parse(&bytes[..])?)
+}
+
+fn sorted<'a, I: Iterator<Item = &'a String>>(names: I) -> Vec<&'a String> {
+    let mut names = names.collect::<Vec<_>>();
+    names.sort();
+    names
+}
+
+fn run(command: Command) -> Result<(), Error> {
+    match command {
+        Command::Generate {
+            wsdl,
+            output,
+            module,
+            derive,
+            visibility,
+            no_client,
+            blocking,
+            no_docs,
+            float_as_decimal,
+        } => {
+            let model = parse(&wsdl)?;
+            let mut builder = Builder::new();
+            if let Some(module) = module.as_ref() {
+                builder.module(module);
+            }
+            for derive in derive.iter() {
+                builder.derive(derive);
+            }
+            builder
+                .visibility(&visibility)
+                .generate_client(!no_client)
+                .blocking_client(blocking)
+                .emit_docs(!no_docs)
+                .float_as_decimal(float_as_decimal);
+            let code = builder.generate(&model)?;
+
+            match output {
+                Some(output) => fs::write(&output, code).map_err(|e| Error::Io(output, e))?,
+                None => print!("{}", code),
+            }
+        }
+        Command::Operations { wsdl } => {
+            let model = parse(&wsdl)?;
+            for name in sorted(model.operations.keys()) {
+                let operation = &model.operations[name];
+                println!(
+                    "{}({}) -> {}",
+                    name,
+                    operation.input.as_deref().unwrap_or_default(),
+                    operation.output.as_deref().unwrap_or("()"),
+                );
+            }
+        }
+        Command::Types { wsdl } => {
+            let model = parse(&wsdl)?;
+            for name in sorted(model.types.keys()) {
+                println!("{}", name);
+            }
+        }
+        Command::Ports { wsdl } => {
+            let model = parse(&wsdl)?;
+            for port in model.ports.iter() {
+                println!(
+                    "{} ({}) {}",
+                    port.name,
+                    port.binding,
+                    port.location.as_deref().unwrap_or_default(),
+                );
+            }
+        }
+        Command::Dump { wsdl } => {
+            let model = parse(&wsdl)?;
+            // going through a `Value` sorts the maps' keys
+            let value = serde_json::to_value(&model)?;
+            println!("{}", serde_json::to_string_pretty(&value)?);
+        }
+        Command::Validate { wsdl } => {
+            let model = parse(&wsdl)?;
+            let tokens = Builder::new().tokens(&model)?;
+            syn::parse_file(&tokens.to_string()).map_err(Error::Syntax)?;
+            eprintln!("{}: ok", wsdl.display());
+        }
+    }
+
+    Ok(())
+}
+
+fn main() {
+    let cli = Cli::parse();
+    if let Err(e) = run(cli.command) {
+        match e {
+            Error::Io(path, e) => eprintln!("error: {}: {}", path.display(), e),
+            Error::Gen(e) => eprintln!("error: {:?}", e),
+            Error::Json(e) => eprintln!("error: {}", e),
+            Error::Syntax(e) => eprintln!("error: generated code does not parse: {}", e),
+        }
+        process::exit(1);
+    }
+}
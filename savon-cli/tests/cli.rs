@@ -0,0 +1,93 @@
+use assert_cmd::Command;
+use std::path::PathBuf;
+
+fn wsdl(name: &str) -> String {
+    format!("{}/../savon-test/{}", env!("CARGO_MANIFEST_DIR"), name)
+}
+
+fn savon(args: &[&str]) -> assert_cmd::assert::Assert {
+    Command::cargo_bin("savon").unwrap().args(args).assert()
+}
+
+fn stdout(assert: assert_cmd::assert::Assert) -> String {
+    String::from_utf8(assert.success().get_output().stdout.clone()).unwrap()
+}
+
+#[test]
+fn lists_operations_types_and_ports() {
+    let country_info = wsdl("CountryInfoService.wsdl");
+
+    let operations = stdout(savon(&["operations", &country_info]));
+    assert!(operations
+        .lines()
+        .any(|l| l == "CurrencyName(CurrencyNameSoapRequest) -> CurrencyNameSoapResponse"));
+    let mut sorted = operations.lines().collect::<Vec<_>>();
+    sorted.sort_unstable();
+    assert_eq!(operations.lines().collect::<Vec<_>>(), sorted);
+
+    let types = stdout(savon(&["types", &country_info]));
+    assert!(types.lines().any(|l| l == "tContinent"));
+
+    let ports = stdout(savon(&["ports", &country_info]));
+    assert!(ports.contains(
+        "http://webservices.oorsprong.org/websamples.countryinfo/CountryInfoService.wso"
+    ));
+}
+
+#[test]
+fn generates_valid_code() {
+    let features = wsdl("features.wsdl");
+
+    let code = stdout(savon(&["generate", &features, "--blocking"]));
+    let file = syn::parse_file(&code).unwrap();
+    assert!(!file.items.is_empty());
+
+    let output = std::env::temp_dir().join(format!("savon-cli-{}.rs", std::process::id()));
+    let output_path = output.to_str().unwrap();
+    let module = stdout(savon(&[
+        "generate",
+        &features,
+        "--module",
+        "features",
+        "--no-client",
+        "-o",
+        output_path,
+    ]));
+    assert!(module.is_empty());
+    let code = std::fs::read_to_string(&output).unwrap();
+    std::fs::remove_file(&output).unwrap();
+    let file = syn::parse_file(&code).unwrap();
+    assert!(matches!(file.items.as_slice(), [syn::Item::Mod(m)] if m.ident == "features"));
+    assert!(!code.contains("FeaturesService"));
+}
+
+#[test]
+fn dumps_the_model_as_json() {
+    let dump = stdout(savon(&["dump", &wsdl("names.wsdl")]));
+    let model: serde_json::Value = serde_json::from_str(&dump).unwrap();
+    assert!(model["operations"]["Ping"].is_object());
+}
+
+#[test]
+fn validate_reports_errors() {
+    let assert = savon(&["validate", &wsdl("features.wsdl")]).success();
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr).into_owned();
+    assert!(stderr.ends_with("features.wsdl: ok\n"));
+
+    let invalid = std::fs::read_to_string(wsdl("features.wsdl"))
+        .unwrap()
+        .replacen(r#"minOccurs="0""#, r#"minOccurs="none""#, 1);
+    let path: PathBuf =
+        std::env::temp_dir().join(format!("savon-cli-invalid-{}.wsdl", std::process::id()));
+    std::fs::write(&path, invalid).unwrap();
+    let assert = savon(&["validate", path.to_str().unwrap()])
+        .failure()
+        .code(1);
+    std::fs::remove_file(&path).unwrap();
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr).into_owned();
+    assert!(stderr.contains("InvalidOccurence"));
+
+    savon(&["validate", &wsdl("missing.wsdl")])
+        .failure()
+        .code(1);
+}
@@ -0,0 +1,2369 @@
+use crate::lexical;
+use crate::wsdl::{
+    parse, ComplexType, Form, Message, Operation, SimpleType, Type, TypeAttribute, Wsdl, WsdlError,
+};
+use case::CaseExt;
+use proc_macro2::{Ident, Literal, Span, TokenStream, TokenTree};
+use std::{
+    collections::{HashMap, HashSet},
+    fs::File,
+    io::Write,
+};
+
+#[derive(Debug)]
+pub enum GenError {
+    Io(std::io::Error),
+    Wsdl(WsdlError),
+    /// Generated code could not be parsed for pretty-printing.
+    #[cfg(feature = "pretty")]
+    Syntax(syn::Error),
+    /// A [`Builder`] option is not valid Rust.
+    InvalidOption {
+        name: &'static str,
+        value: String,
+    },
+    /// A schema default or fixed value does not parse as its field's type.
+    InvalidDefault {
+        type_name: String,
+        field: String,
+        value: String,
+        reason: String,
+    },
+    /// A declared header's element is neither a parsed type nor of a built-in type.
+    UnknownHeader {
+        operation: String,
+        element: String,
+    },
+    /// An operation's input element has a built-in type, which requests can't send.
+    UnsupportedInput {
+        operation: String,
+        element: String,
+    },
+}
+
+impl From<std::io::Error> for GenError {
+    fn from(e: std::io::Error) -> Self {
+        GenError::Io(e)
+    }
+}
+
+impl From<WsdlError> for GenError {
+    fn from(e: WsdlError) -> Self {
+        GenError::Wsdl(e)
+    }
+}
+
+#[cfg(feature = "pretty")]
+impl From<syn::Error> for GenError {
+    fn from(e: syn::Error) -> Self {
+        GenError::Syntax(e)
+    }
+}
+
+/// Code generation options, e.g. in a `build.rs`:
+///
+/// ```no_run
+/// let out_dir = std::env::var("OUT_DIR").unwrap();
+/// savon_gen::gen::Builder::new()
+///     .module("stock_quote")
+///     .derive("PartialEq")
+///     .type_name("TradePriceRequest", "PriceRequest")
+///     .field_name("TradePriceRequest", "tickerSymbol", "symbol")
+///     .write("./assets/example.wsdl", &out_dir)
+///     .unwrap();
+/// ```
+#[derive(Clone, Debug)]
+pub struct Builder {
+    module: Option<String>,
+    file_name: Option<String>,
+    derives: Vec<String>,
+    type_names: HashMap<String, String>,
+    field_names: HashMap<(String, String), String>,
+    visibility: String,
+    client: bool,
+    blocking: bool,
+    docs: bool,
+    float_as_decimal: bool,
+}
+
+impl Default for Builder {
+    fn default() -> Self {
+        Builder {
+            module: None,
+            file_name: None,
+            derives: vec![],
+            type_names: HashMap::new(),
+            field_names: HashMap::new(),
+            visibility: "pub".to_string(),
+            client: true,
+            blocking: false,
+            docs: true,
+            float_as_decimal: false,
+        }
+    }
+}
+
+impl Builder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Wrap the generated code in a module with that name.
+    pub fn module(&mut self, name: &str) -> &mut Self {
+        self.module = Some(name.to_string());
+        self
+    }
+
+    /// Name of the file written in the output directory, instead of the WSDL file name
+    /// in snake case with a `.rs` extension.
+    pub fn file_name(&mut self, name: &str) -> &mut Self {
+        self.file_name = Some(name.to_string());
+        self
+    }
+
+    /// Add a derive to generated types and messages, like `PartialEq` or
+    /// `serde::Serialize`.
+    ///
+    /// `savon::gen::Nillable` fields support serde with savon's `serde` feature.
+    pub fn derive(&mut self, path: &str) -> &mut Self {
+        self.derives.push(path.to_string());
+        self
+    }
+
+    /// Rust name of the schema type or element `xml_name`.
+    pub fn type_name(&mut self, xml_name: &str, rust_name: &str) -> &mut Self {
+        self.type_names
+            .insert(xml_name.to_string(), rust_name.to_string());
+        self
+    }
+
+    /// Rust name of the field for the element or attribute `xml_name` of the schema type
+    /// `xml_type`. The XML name is still used on the wire.
+    pub fn field_name(&mut self, xml_type: &str, xml_name: &str, rust_name: &str) -> &mut Self {
+        self.field_names.insert(
+            (xml_type.to_string(), xml_name.to_string()),
+            rust_name.to_string(),
+        );
+        self
+    }
+
+    /// Visibility of generated items, `pub` by default.
+    pub fn visibility(&mut self, visibility: &str) -> &mut Self {
+        self.visibility = visibility.to_string();
+        self
+    }
+
+    /// Whether to generate the service client, `true` by default.
+    pub fn generate_client(&mut self, client: bool) -> &mut Self {
+        self.client = client;
+        self
+    }
+
+    /// Whether to generate a blocking client in a `blocking` module, sharing the `types`
+    /// and `messages` modules, `false` by default. Its `new` and `with_client` constructors,
+    /// using `reqwest::blocking::Client`, only exist with savon's `blocking` feature.
+    ///
+    /// With `generate_client(false)`, only the blocking client is generated.
+    pub fn blocking_client(&mut self, blocking: bool) -> &mut Self {
+        self.blocking = blocking;
+        self
+    }
+
+    /// Whether to emit doc comments on generated items, `true` by default.
+    pub fn emit_docs(&mut self, docs: bool) -> &mut Self {
+        self.docs = docs;
+        self
+    }
+
+    /// Whether `xs:float` and `xs:double` map to `rust_decimal::Decimal` like `xs:decimal`
+    /// instead of `f64`, `false` by default. `INF` and `NaN` can't be represented then.
+    pub fn float_as_decimal(&mut self, float_as_decimal: bool) -> &mut Self {
+        self.float_as_decimal = float_as_decimal;
+        self
+    }
+}
+
+/// Generates code for the WSDL file at `path` with the default options. The error is
+/// logged; use [`Builder::write`] to get it.
+// kept returning `()` so existing build scripts still compile
+#[allow(clippy::result_unit_err)]
+pub fn gen_write(path: &str, out: &str) -> Result<(), ()> {
+    Builder::new().write(path, out).map_err(|e| {
+        error!("could not generate code for {}: {:?}", path, e);
+    })
+}
+
+#[derive(Clone, Copy)]
+enum Case {
+    /// Types, messages and enum variants.
+    Camel,
+    /// Fields, operations and parameters.
+    Snake,
+}
+
+/// Whether client methods are `async`, or block until the response arrives.
+#[derive(Clone, Copy)]
+enum Calls {
+    Async,
+    Blocking,
+}
+
+impl Calls {
+    fn asyncness(self) -> TokenStream {
+        match self {
+            Calls::Async => quote! { async },
+            Calls::Blocking => quote! {},
+        }
+    }
+
+    /// Wait for a request sent with `savon::http`.
+    fn send(self, future: TokenStream) -> TokenStream {
+        match self {
+            Calls::Async => quote! { #future.await },
+            Calls::Blocking => quote! { savon::blocking::block_on(#future) },
+        }
+    }
+
+    /// Wait for another method of the client.
+    fn call(self, call: TokenStream) -> TokenStream {
+        match self {
+            Calls::Async => quote! { #call.await },
+            Calls::Blocking => call,
+        }
+    }
+
+    /// Default transport of the client.
+    fn reqwest_client(self) -> TokenStream {
+        match self {
+            Calls::Async => quote! { savon::internal::reqwest::Client },
+            Calls::Blocking => quote! { savon::internal::reqwest::blocking::Client },
+        }
+    }
+}
+
+/// Rust keywords, usable as raw identifiers.
+const KEYWORDS: &[&str] = &[
+    "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "do", "dyn",
+    "else", "enum", "extern", "false", "final", "fn", "for", "gen", "if", "impl", "in", "let",
+    "loop", "macro", "match", "mod", "move", "mut", "override", "priv", "pub", "ref", "return",
+    "static", "struct", "trait", "true", "try", "type", "typeof", "unsafe", "unsized", "use",
+    "virtual", "where", "while", "yield",
+];
+
+/// Names used by the generated code, which types and messages must not shadow.
+const RESERVED_TYPE_NAMES: &[&str] = &[
+    "Box",
+    "Default",
+    "LiteralRequest",
+    "LiteralResponse",
+    "Option",
+    "Result",
+    "String",
+    "Vec",
+];
+
+/// Valid Rust name for an XML name: characters outside of `[A-Za-z0-9_]` become
+/// underscores, names starting with a digit get an underscore prefix and keywords become
+/// raw identifiers (or get an underscore suffix when they cannot be raw).
+fn rust_name(xml_name: &str, case: Case) -> String {
+    let cleaned = xml_name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect::<String>();
+    let mut name = match case {
+        Case::Camel => cleaned.to_camel().replace('_', ""),
+        Case::Snake => {
+            let snake = cleaned.to_snake();
+            let mut name = String::with_capacity(snake.len());
+            for c in snake.chars() {
+                if !(c == '_' && name.ends_with('_')) {
+                    name.push(c);
+                }
+            }
+            name
+        }
+    };
+
+    if name.is_empty() || name.starts_with(|c: char| c.is_ascii_digit()) {
+        name.insert(0, '_');
+    }
+    match name.as_str() {
+        "_" | "self" | "Self" | "super" | "crate" => format!("{}_", name),
+        n if KEYWORDS.contains(&n) => format!("r#{}", n),
+        _ => name,
+    }
+}
+
+/// `name`, or `name` with a numeric suffix if it is already used.
+fn unique_name(name: String, separator: &str, used: &mut HashSet<String>) -> String {
+    let mut candidate = name.clone();
+    let mut i = 2;
+    while !used.insert(candidate.clone()) {
+        candidate = format!("{}{}{}", name.trim_start_matches("r#"), separator, i);
+        i += 1;
+    }
+    candidate
+}
+
+/// Identifier for a name which may be a raw one, like `r#type`.
+fn to_ident(name: &str) -> Ident {
+    match name.strip_prefix("r#") {
+        Some(raw) => Ident::new_raw(raw, Span::call_site()),
+        None => Ident::new(name, Span::call_site()),
+    }
+}
+
+/// Fields of recursive types which need a `Box`: those whose type leads back to the
+/// type containing them without going through a `Vec`, as `(type, field)` names.
+fn recursive_fields(types: &HashMap<String, Type>) -> HashSet<(String, String)> {
+    // types directly contained by a type
+    let contained = |name: &str| -> Vec<&String> {
+        match types.get(name) {
+            Some(Type::Complex(c)) => c
+                .fields
+                .iter()
+                .filter(|(_, (attributes, _))| !attributes.is_repeated())
+                .filter_map(|(_, (_, field_type))| match field_type {
+                    SimpleType::Complex(t) => Some(t),
+                    _ => None,
+                })
+                .collect(),
+            _ => vec![],
+        }
+    };
+    let reaches = |from: &String, to: &str| {
+        let mut seen = HashSet::new();
+        let mut stack = vec![from];
+        while let Some(name) = stack.pop() {
+            if name == to {
+                return true;
+            }
+            if seen.insert(name) {
+                stack.extend(contained(name));
+            }
+        }
+        false
+    };
+
+    let mut boxed = HashSet::new();
+    for (name, t) in types.iter() {
+        if let Type::Complex(c) = t {
+            for (field_name, (attributes, field_type)) in c.fields.iter() {
+                if let SimpleType::Complex(field_type) = field_type {
+                    if !attributes.is_repeated() && reaches(field_type, name) {
+                        boxed.insert((name.clone(), field_name.clone()));
+                    }
+                }
+            }
+        }
+    }
+    boxed
+}
+
+/// Types which cannot implement `Default`: those whose required fields lead back to
+/// themselves, as their default value would be infinite, and the types requiring them.
+fn without_default(types: &HashMap<String, Type>) -> HashSet<String> {
+    // types of the required fields of a type
+    let required = |name: &str| -> Vec<&String> {
+        match types.get(name) {
+            Some(Type::Complex(c)) => c
+                .fields
+                .iter()
+                .filter(|(_, (attributes, field_type))| {
+                    matches!(cardinality(attributes, field_type), Cardinality::Required)
+                })
+                .filter_map(|(_, (_, field_type))| match field_type {
+                    SimpleType::Complex(t) => Some(t),
+                    _ => None,
+                })
+                .collect(),
+            _ => vec![],
+        }
+    };
+    // types reached through at least one required field
+    let reached = |from: &str| {
+        let mut seen = HashSet::new();
+        let mut stack = required(from);
+        while let Some(name) = stack.pop() {
+            if seen.insert(name) {
+                stack.extend(required(name));
+            }
+        }
+        seen
+    };
+
+    let cyclic = types
+        .keys()
+        .filter(|name| reached(name).contains(name))
+        .collect::<HashSet<_>>();
+    types
+        .keys()
+        .filter(|name| cyclic.contains(name) || reached(name).iter().any(|t| cyclic.contains(t)))
+        .cloned()
+        .collect()
+}
+
+/// Map entries ordered by name, so the generated code does not depend on hashing.
+fn sorted<V>(map: &HashMap<String, V>) -> Vec<(&String, &V)> {
+    let mut entries = map.iter().collect::<Vec<_>>();
+    entries.sort_by(|a, b| a.0.cmp(b.0));
+    entries
+}
+
+/// Returns the element type of a message following the document/literal wrapped
+/// convention: a single part whose element is a complex type (a sequence of children),
+/// named `element_name` (the operation for requests, `{operation}Response` for responses).
+fn wrapped_type<'a>(
+    messages: &'a HashMap<String, Message>,
+    types: &'a HashMap<String, Type>,
+    message_name: &str,
+    element_name: &str,
+) -> Option<(&'a str, &'a ComplexType)> {
+    let message = messages.get(message_name)?;
+    if element_name != message.part_element {
+        return None;
+    }
+    match types.get(&message.part_element)? {
+        Type::Complex(c) => Some((message.part_element.as_str(), c)),
+        Type::Simple(_) => None,
+    }
+}
+
+/// Error of an operation's result: its fault enum, or `()` without declared faults.
+fn error_type(context: &Context, name: &str, operation: &Operation) -> TokenStream {
+    match operation.faults.as_ref() {
+        Some(_) => {
+            let error_type = context.fault_ident(name);
+            quote! { messages::#error_type }
+        }
+        None => quote! { () },
+    }
+}
+
+fn gen_operations(
+    context: &Context,
+    operations: &HashMap<String, Operation>,
+    messages: &HashMap<String, Message>,
+    types: &HashMap<String, Type>,
+    target_namespace: &Literal,
+    addressing: bool,
+    calls: Calls,
+) -> Result<Vec<TokenStream>, GenError> {
+    sorted(operations).into_iter().map(|(name, operation)| {
+        let input = match operation.input.as_ref() {
+            Some(input) => input,
+            None => return Ok(quote! {}),
+        };
+        if let Some(message) = messages.get(input) {
+            if let Some(Type::Simple(_)) = types.get(&message.part_element) {
+                return Err(GenError::UnsupportedInput {
+                    operation: name.clone(),
+                    element: message.part_element.clone(),
+                });
+            }
+        }
+        let op_name = context.operation_ident(name);
+        let input_name = to_ident(&rust_name(input, Case::Snake));
+        let input_type = context.message_ident(input);
+        let full_input_type = quote!{ messages::#input_type };
+        let vis = context.vis();
+        let default_doc = format!("Calls the `{}` operation.", name);
+        let doc = context.doc(operation.documentation.as_deref().or(Some(&default_doc)));
+        let asyncness = calls.asyncness();
+
+        // document/literal: the body holds the input part's element, in its schema's namespace
+        let (target_namespace, op_str) = match messages
+            .get(input)
+            .and_then(|m| types.get(&m.part_element).map(|t| (m, t)))
+        {
+            Some((m, Type::Complex(ComplexType { namespace: Some(ns), .. }))) => {
+                (Literal::string(ns), Literal::string(&m.part_element))
+            }
+            _ => (target_namespace.clone(), Literal::string(name)),
+        };
+
+        let (header_params, _, header_elements) = gen_input_headers(context, operation, types)?;
+        // WS-Addressing services expect the action in every request
+        let action = operation
+            .input_action
+            .as_ref()
+            .filter(|_| addressing)
+            .map(|action| quote! { Some(#action.to_string()) });
+        let action_field = action.as_ref().map(|action| quote! { action: #action, });
+        let plain = header_params.is_empty() && action.is_none();
+        let request = quote! {
+            savon::http::Request {
+                headers: vec![#(#header_elements),*],
+                #action_field
+                ..Default::default()
+            }
+        };
+
+        let method = match (operation.output.as_ref(), operation.faults.as_ref()) {
+            (None, None) if plain => {
+                let send = calls.send(quote! {
+                    savon::http::one_way(&*self.transport, &self.base_url, &self.options, #target_namespace, #op_str, &#input_name)
+                });
+                quote! {
+                    #doc
+                    #vis #asyncness fn #op_name(&self, #input_name: #full_input_type) -> Result<(), savon::Error> {
+                        #send
+                    }
+                }
+            }
+            (None, None) => {
+                let send = calls.send(quote! {
+                    savon::http::one_way_with(&*self.transport, &self.base_url, &self.options, #target_namespace, #op_str, &#input_name, &request)
+                });
+                quote! {
+                    #doc
+                    #vis #asyncness fn #op_name(&self, #input_name: #full_input_type, #(#header_params),*) -> Result<(), savon::Error> {
+                        let request = #request;
+                        #send
+                    }
+                }
+            }
+            (None, Some(_)) => quote! {},
+            (Some(out), _) if plain => {
+                let output_type = context.message_ident(out);
+                let full_output_type = quote!{ messages::#output_type };
+                let error_type = error_type(context, name, operation);
+                let send = calls.send(quote! {
+                    savon::http::request_response(&*self.transport, &self.base_url, &self.options, #target_namespace, #op_str, &#input_name)
+                });
+
+                quote! {
+                    #doc
+                    #vis #asyncness fn #op_name(&self, #input_name: #full_input_type) -> Result<Result<#full_output_type, #error_type>, savon::Error> {
+                        #send
+                    }
+                }
+            }
+            (Some(out), _) => {
+                let output_type = context.message_ident(out);
+                let full_output_type = quote!{ messages::#output_type };
+                let error_type = error_type(context, name, operation);
+                let send = calls.send(quote! {
+                    savon::http::request_response_with(&*self.transport, &self.base_url, &self.options, #target_namespace, #op_str, &#input_name, &request)
+                });
+
+                quote! {
+                    #doc
+                    #vis #asyncness fn #op_name(&self, #input_name: #full_input_type, #(#header_params),*) -> Result<Result<#full_output_type, #error_type>, savon::Error> {
+                        let request = #request;
+                        #send.map(|r| r.map(|reply| reply.output))
+                    }
+                }
+            }
+        };
+
+        let unwrapped = gen_unwrapped_operation(context, name, operation, messages, types, calls)?;
+        let with_attachments = gen_attachments_operation(
+            context, name, operation, types, &target_namespace, &op_str, action.as_ref(), calls,
+        )?;
+
+        Ok(quote! {
+            #method
+
+            #unwrapped
+
+            #with_attachments
+        })
+    }).collect()
+}
+
+/// Parameters for the declared headers of an operation's input, their names, and the
+/// elements they are sent as.
+#[allow(clippy::type_complexity)]
+fn gen_input_headers(
+    context: &Context,
+    operation: &Operation,
+    types: &HashMap<String, Type>,
+) -> Result<(Vec<TokenStream>, Vec<Ident>, Vec<TokenStream>), GenError> {
+    let mut params = vec![];
+    let mut names = vec![];
+    let mut elements = vec![];
+    for header in operation.input_headers.iter() {
+        let mut param = rust_name(&header.part, Case::Snake);
+        if !param.ends_with("header") {
+            param.push_str("_header");
+        }
+        let param = to_ident(&param);
+        let element = Literal::string(&header.element);
+
+        match (header.simple_type.as_ref(), types.get(&header.element)) {
+            (Some(simple_type), _) => {
+                let ty = gen_base_type(context, simple_type, &quote! { types:: });
+                let to_text = gen_to_text(simple_type);
+                let namespace = match header.namespace.as_ref() {
+                    Some(ns) => quote! { Some(#ns) },
+                    None => quote! { None },
+                };
+                params.push(quote! { #param: #ty });
+                elements.push(quote! {
+                    savon::gen::to_text_element(#element, #namespace, #to_text(&#param))
+                });
+            }
+            (None, Some(Type::Complex(c))) => {
+                let ty = context.type_ident(&header.element);
+                let namespace = match c.namespace.as_ref() {
+                    Some(ns) => quote! { Some(#ns) },
+                    None => quote! { None },
+                };
+                params.push(quote! { #param: types::#ty });
+                elements.push(quote! { savon::gen::to_element(#element, #namespace, &#param) });
+            }
+            _ => {
+                return Err(GenError::UnknownHeader {
+                    operation: operation.name.clone(),
+                    element: header.element.clone(),
+                })
+            }
+        }
+        names.push(param);
+    }
+    Ok((params, names, elements))
+}
+
+/// Generates a method sending and receiving attachments alongside the envelope, for
+/// operations with a `mime:multipartRelated` binding (SOAP with Attachments).
+#[allow(clippy::too_many_arguments)]
+fn gen_attachments_operation(
+    context: &Context,
+    name: &str,
+    operation: &Operation,
+    types: &HashMap<String, Type>,
+    target_namespace: &Literal,
+    op_str: &Literal,
+    action: Option<&TokenStream>,
+    calls: Calls,
+) -> Result<TokenStream, GenError> {
+    let input = match operation.input.as_ref() {
+        Some(input) => input,
+        None => return Ok(quote! {}),
+    };
+    if operation.input_attachments.is_empty() && operation.output_attachments.is_empty() {
+        return Ok(quote! {});
+    }
+
+    let method_name = context.attachments_ident(name);
+    let input_name = to_ident(&rust_name(input, Case::Snake));
+    let input_type = context.message_ident(input);
+    let vis = context.vis();
+
+    let mut doc = format!(
+        "Calls the `{}` operation, with attachments alongside the envelope.",
+        name
+    );
+    for (direction, parts) in [
+        ("Input", &operation.input_attachments),
+        ("Output", &operation.output_attachments),
+    ] {
+        if !parts.is_empty() {
+            doc.push_str(&format!("\n\n{} attachments:", direction));
+        }
+        for part in parts {
+            doc.push_str(&format!("\n- `{}`", part.part));
+            if !part.content_types.is_empty() {
+                doc.push_str(&format!(": {}", part.content_types.join(", ")));
+            }
+        }
+    }
+    let doc = context.doc(Some(&doc));
+    let (header_params, _, header_elements) = gen_input_headers(context, operation, types)?;
+    let action = action.cloned().unwrap_or_else(|| quote! { None });
+    let request = quote! {
+        savon::http::Request {
+            headers: vec![#(#header_elements),*],
+            attachments,
+            action: #action,
+        }
+    };
+    let asyncness = calls.asyncness();
+
+    Ok(
+        match (operation.output.as_ref(), operation.faults.as_ref()) {
+            (None, None) => {
+                let send = calls.send(quote! {
+                savon::http::one_way_with(&*self.transport, &self.base_url, &self.options, #target_namespace, #op_str, &#input_name, &request)
+            });
+                quote! {
+                    #doc
+                    #vis #asyncness fn #method_name(
+                        &self,
+                        #input_name: messages::#input_type,
+                        #(#header_params,)*
+                        attachments: Vec<savon::http::Attachment>,
+                    ) -> Result<(), savon::Error> {
+                        let request = #request;
+                        #send
+                    }
+                }
+            }
+            (Some(out), _) => {
+                let output_type = context.message_ident(out);
+                let error_type = error_type(context, name, operation);
+                let send = calls.send(quote! {
+                savon::http::request_response_with(&*self.transport, &self.base_url, &self.options, #target_namespace, #op_str, &#input_name, &request)
+            });
+                quote! {
+                    #doc
+                    #vis #asyncness fn #method_name(
+                        &self,
+                        #input_name: messages::#input_type,
+                        #(#header_params,)*
+                        attachments: Vec<savon::http::Attachment>,
+                    ) -> Result<Result<(messages::#output_type, Vec<savon::http::Attachment>), #error_type>, savon::Error> {
+                        let request = #request;
+                        #send.map(|r| r.map(|reply| (reply.output, reply.attachments)))
+                    }
+                }
+            }
+            _ => quote! {},
+        },
+    )
+}
+
+/// Generates a method taking the wrapper element's children as parameters, and
+/// returning the response wrapper's single child, for operations following the
+/// document/literal wrapped convention (as wsimport and svcutil do).
+fn gen_unwrapped_operation(
+    context: &Context,
+    name: &str,
+    operation: &Operation,
+    messages: &HashMap<String, Message>,
+    types: &HashMap<String, Type>,
+    calls: Calls,
+) -> Result<TokenStream, GenError> {
+    let input = match operation.input.as_ref() {
+        Some(input) => input,
+        None => return Ok(quote! {}),
+    };
+    let (input_element, input_type) = match wrapped_type(messages, types, input, name) {
+        Some(wrapped) => wrapped,
+        None => return Ok(quote! {}),
+    };
+
+    let op_name = context.operation_ident(name);
+    let unwrapped_name = context.unwrapped_ident(name);
+    let message_type = context.message_ident(input);
+    let element_type = context.type_ident(input_element);
+    let vis = context.vis();
+    let mut doc = format!(
+        "Calls the `{}` operation with the children of its wrapper element.",
+        name
+    );
+    if let Some(documentation) = operation.documentation.as_ref() {
+        doc = format!("{}\n\n{}", doc, documentation);
+    }
+    let doc = context.doc(Some(&doc));
+
+    let types_path = quote! { types:: };
+    let params = input_type
+        .fields
+        .iter()
+        .map(|(field_name, (attributes, field_type))| {
+            let fname = context.field_ident(input_element, field_name);
+            let boxed = context.is_boxed(input_element, field_name);
+            let ft = gen_field_type(context, attributes, field_type, boxed, &types_path);
+            quote! { #fname: #ft }
+        })
+        .collect::<Vec<_>>();
+    let field_names = input_type
+        .fields
+        .iter()
+        .map(|(field_name, _)| context.field_ident(input_element, field_name))
+        .collect::<Vec<_>>();
+    let (header_params, header_names, _) = gen_input_headers(context, operation, types)?;
+    let params = params.into_iter().chain(header_params).collect::<Vec<_>>();
+    let request = quote! {
+        messages::#message_type(types::#element_type { #(#field_names),* }), #(#header_names),*
+    };
+    let asyncness = calls.asyncness();
+    let call = calls.call(quote! { self.#op_name(#request) });
+
+    Ok(
+        match (operation.output.as_ref(), operation.faults.as_ref()) {
+            (None, None) => quote! {
+                #doc
+                #[allow(clippy::too_many_arguments)]
+                #vis #asyncness fn #unwrapped_name(&self, #(#params),*) -> Result<(), savon::Error> {
+                    #call
+                }
+            },
+            (Some(out), _) => {
+                // the response wrapper is named after the operation too
+                let response_element = format!("{}Response", name);
+                let (output_element, output_type) =
+                    match wrapped_type(messages, types, out, &response_element) {
+                        Some(wrapped) => wrapped,
+                        None => return Ok(quote! {}),
+                    };
+
+                let (ret, extract) = match output_type.fields.as_slice() {
+                    [] => (quote! { () }, quote! { |_| () }),
+                    [(field_name, (attributes, field_type))] => {
+                        let fname = context.field_ident(output_element, field_name);
+                        let boxed = context.is_boxed(output_element, field_name);
+                        let ft =
+                            gen_field_type(context, attributes, field_type, boxed, &types_path);
+                        (ft, quote! { |o| o.0.#fname })
+                    }
+                    _ => {
+                        let ty = context.type_ident(output_element);
+                        (quote! { types::#ty }, quote! { |o| o.0 })
+                    }
+                };
+                let error_type = error_type(context, name, operation);
+
+                quote! {
+                    #doc
+                    #[allow(clippy::too_many_arguments)]
+                    #vis #asyncness fn #unwrapped_name(&self, #(#params),*) -> Result<Result<#ret, #error_type>, savon::Error> {
+                        #call.map(|r| r.map(#extract))
+                    }
+                }
+            }
+            (None, Some(_)) => quote! {},
+        },
+    )
+}
+
+/// How many times a field's element may appear, and whether it may be nil.
+enum Cardinality {
+    /// `T`
+    Required,
+    /// `Option<T>`, `None` being serialized as an absent element.
+    Optional,
+    /// `Option<T>`, `None` being serialized as `xsi:nil`.
+    Nillable,
+    /// `savon::gen::Nillable<T>`
+    OptionalNillable,
+    /// `Vec<T>`
+    Repeated,
+    /// `Vec<Option<T>>`, `None` items being serialized as `xsi:nil`.
+    RepeatedNillable,
+    /// `T`, the schema's default or fixed value being used when absent.
+    Defaulted,
+}
+
+fn cardinality(attributes: &TypeAttribute, field_type: &SimpleType) -> Cardinality {
+    let defaulted = schema_default(attributes, field_type).is_some()
+        && (attributes.is_optional() || attributes.fixed.is_some());
+
+    match (
+        attributes.is_repeated(),
+        attributes.is_optional(),
+        attributes.nillable,
+    ) {
+        (true, _, false) => Cardinality::Repeated,
+        (true, _, true) => Cardinality::RepeatedNillable,
+        _ if defaulted => Cardinality::Defaulted,
+        (false, false, false) => Cardinality::Required,
+        (false, true, false) => Cardinality::Optional,
+        (false, false, true) => Cardinality::Nillable,
+        (false, true, true) => Cardinality::OptionalNillable,
+    }
+}
+
+/// Fixed or default value from the schema, only supported for simple types.
+fn schema_default<'a>(attributes: &'a TypeAttribute, field_type: &SimpleType) -> Option<&'a str> {
+    match field_type {
+        SimpleType::Complex(_) => None,
+        _ => attributes
+            .fixed
+            .as_deref()
+            .or(attributes.default.as_deref()),
+    }
+}
+
+/// Checks that the schema default or fixed values of a type parse as their fields' types,
+/// so the generated code can rely on them.
+fn check_defaults(context: &Context, type_name: &str, c: &ComplexType) -> Result<(), GenError> {
+    for (field_name, (attributes, field_type)) in c.fields.iter().chain(c.attributes.iter()) {
+        let value = match schema_default(attributes, field_type) {
+            Some(value) => value,
+            None => continue,
+        };
+        let field_type = match field_type {
+            SimpleType::Float if context.builder.float_as_decimal => &SimpleType::Decimal,
+            field_type => field_type,
+        };
+        lexical::check(field_type, value).map_err(|reason| GenError::InvalidDefault {
+            type_name: type_name.to_string(),
+            field: field_name.clone(),
+            value: value.to_string(),
+            reason,
+        })?;
+    }
+    Ok(())
+}
+
+/// Namespace a field's element is serialized in, if it is qualified.
+fn field_namespace(c: &ComplexType, attributes: &TypeAttribute) -> Option<Literal> {
+    match (attributes.form, c.namespace.as_ref()) {
+        (Form::Qualified, Some(ns)) => Some(Literal::string(ns)),
+        _ => None,
+    }
+}
+
+/// Expression parsing a schema default or fixed value.
+fn gen_parse_text(
+    context: &Context,
+    name: &str,
+    value: &str,
+    field_type: &SimpleType,
+) -> TokenStream {
+    let from_text = gen_from_text(context, field_type);
+    let name = Literal::string(name);
+    quote! { #from_text(#name, #value) }
+}
+
+/// Function parsing the text of a simple type, as `fn(name: &str, text: &str)`.
+fn gen_from_text(context: &Context, field_type: &SimpleType) -> TokenStream {
+    match field_type {
+        SimpleType::Base64Binary => quote! { savon::rpser::xml::from_base64 },
+        SimpleType::HexBinary => quote! { savon::rpser::xml::from_hex },
+        _ => {
+            let ty = gen_base_type(context, field_type, &quote! {});
+            quote! { <#ty as savon::rpser::xml::FromXmlText>::from_xml_text }
+        }
+    }
+}
+
+/// Function writing a reference to a simple type value as text.
+fn gen_to_text(field_type: &SimpleType) -> TokenStream {
+    match field_type {
+        SimpleType::Base64Binary => quote! { savon::rpser::xml::to_base64 },
+        SimpleType::HexBinary => quote! { savon::rpser::xml::to_hex },
+        _ => quote! { savon::rpser::xml::ToXmlText::to_xml_text },
+    }
+}
+
+/// Function parsing an `&xmltree::Element` into a `Result<_, savon::Error>`.
+fn gen_parse_value(context: &Context, field_type: &SimpleType, boxed: bool) -> TokenStream {
+    match field_type {
+        SimpleType::Complex(s) if boxed => {
+            let complex_type = context.type_ident(s);
+            quote! { |e: &xmltree::Element| #complex_type::from_element(e).map(Box::new) }
+        }
+        SimpleType::Complex(s) => {
+            let complex_type = context.type_ident(s);
+            quote! { #complex_type::from_element }
+        }
+        // MTOM attachments are read without going through base64
+        SimpleType::Base64Binary => quote! { savon::rpser::xop::binary_value },
+        _ => {
+            let from_text = gen_from_text(context, field_type);
+            quote! {
+                |e: &xmltree::Element| {
+                    let text = e.get_text().unwrap_or_default();
+                    #from_text(&e.name, &text).map_err(savon::Error::from)
+                }
+            }
+        }
+    }
+}
+
+fn gen_base_type(
+    context: &Context,
+    field_type: &SimpleType,
+    types_path: &TokenStream,
+) -> TokenStream {
+    match field_type {
+        SimpleType::Boolean => quote! { bool },
+        SimpleType::String => quote! { String },
+        SimpleType::Float if context.builder.float_as_decimal => {
+            quote! { savon::internal::rust_decimal::Decimal }
+        }
+        SimpleType::Float => quote! { f64 },
+        SimpleType::Decimal => quote! { savon::internal::rust_decimal::Decimal },
+        SimpleType::Int => quote! { i64 },
+        SimpleType::DateTime => {
+            quote! { savon::internal::chrono::DateTime<savon::internal::chrono::FixedOffset> }
+        }
+        SimpleType::Date => quote! { savon::internal::chrono::NaiveDate },
+        SimpleType::Time => quote! { savon::internal::chrono::NaiveTime },
+        SimpleType::Duration => quote! { savon::rpser::xml::Duration },
+        SimpleType::Base64Binary | SimpleType::HexBinary => quote! { Vec<u8> },
+        SimpleType::Complex(s) => {
+            let ty = context.type_ident(s);
+            quote! { #types_path #ty }
+        }
+    }
+}
+
+/// Rust type of a field, `types_path` being the path to the `types` module from
+/// where the type is used, and `boxed` whether the field is part of a cycle of types.
+fn gen_field_type(
+    context: &Context,
+    attributes: &TypeAttribute,
+    field_type: &SimpleType,
+    boxed: bool,
+    types_path: &TokenStream,
+) -> TokenStream {
+    let ft = gen_base_type(context, field_type, types_path);
+    let ft = if boxed {
+        quote! { Box<#ft> }
+    } else {
+        ft
+    };
+
+    match cardinality(attributes, field_type) {
+        Cardinality::Required | Cardinality::Defaulted => ft,
+        Cardinality::Optional | Cardinality::Nillable => quote! { Option<#ft> },
+        Cardinality::OptionalNillable => quote! { savon::gen::Nillable<#ft> },
+        Cardinality::Repeated => quote! { Vec<#ft> },
+        Cardinality::RepeatedNillable => quote! { Vec<Option<#ft>> },
+    }
+}
+
+fn gen_types(
+    context: &Context,
+    types: &HashMap<String, Type>,
+) -> Result<Vec<TokenStream>, GenError> {
+    let simple_types = sorted(types)
+        .into_iter()
+        .filter_map(|(name, t)| match t {
+            Type::Simple(simple_type) => Some(gen_simple_type(context, name, simple_type)),
+            Type::Complex(_) => None,
+        })
+        .collect::<Vec<_>>();
+    let types = sorted(types)
+        .into_iter()
+        .filter_map(|(name, t)| match t {
+            Type::Complex(c) => Some((name, c)),
+            Type::Simple(_) => None,
+        })
+        .collect::<Vec<_>>();
+    for (name, c) in types.iter() {
+        check_defaults(context, name, c)?;
+    }
+
+    Ok(types.into_iter()
+            .map(|(name, c)| {
+                let type_name = context.type_ident(name);
+                let vis = context.vis();
+                let default_doc = format!("`{}` schema type.", name);
+                let doc = context.doc(c.documentation.as_deref().or(Some(&default_doc)));
+
+                let fields = c
+                    .fields
+                    .iter()
+                    .chain(c.attributes.iter())
+                    .map(|(field_name, (attributes, field_type))| {
+                        let fname = context.field_ident(name, field_name);
+                        let boxed = context.is_boxed(name, field_name);
+                        let ft = gen_field_type(context, attributes, field_type, boxed, &quote! {});
+                        let doc = context.doc(attributes.documentation.as_deref());
+
+                        quote! {
+                            #doc
+                            #vis #fname: #ft,
+                        }
+                    })
+                    .collect::<Vec<_>>();
+
+                let fields_serialize_impl = c
+                    .fields
+                    .iter()
+                    .map(|(field_name, (attributes, field_type))| {
+                        let fname = context.field_ident(name, field_name);
+                        let ftype = Literal::string(field_name);
+                        let prefix = match field_namespace(c, attributes) {
+                            Some(ns) => quote! { xmltree::Element::node(#ftype).with_namespace(#ns) },
+                            None => quote! { xmltree::Element::node(#ftype) },
+                        };
+                        let to_text = gen_to_text(field_type);
+                        let to_element = match field_type {
+                            SimpleType::Complex(_s) => quote! {
+                                #prefix.with_attributes(i.to_attributes()).with_children(i.to_elements())
+                            },
+                            SimpleType::Base64Binary => quote! {
+                                savon::rpser::xop::binary_element(#prefix, i)
+                            },
+                            _ => quote! {
+                                #prefix.with_text(#to_text(i))
+                            },
+                        };
+
+                        let nil = quote! { #prefix.with_nil() };
+
+                        match cardinality(attributes, field_type) {
+                            Cardinality::Required => quote! { vec![{ let i = &self.#fname; #to_element }] },
+                            Cardinality::Defaulted => match attributes.fixed.as_ref() {
+                                Some(fixed) => quote! { vec![#prefix.with_text(#fixed)] },
+                                None => quote! { vec![{ let i = &self.#fname; #to_element }] },
+                            },
+                            Cardinality::Optional | Cardinality::Repeated => {
+                                quote! { self.#fname.iter().map(|i| #to_element).collect() }
+                            }
+                            Cardinality::Nillable => quote! {
+                                vec![match &self.#fname {
+                                    Some(i) => #to_element,
+                                    None => #nil,
+                                }]
+                            },
+                            Cardinality::OptionalNillable => quote! {
+                                match &self.#fname {
+                                    savon::gen::Nillable::Absent => vec![],
+                                    savon::gen::Nillable::Nil => vec![#nil],
+                                    savon::gen::Nillable::Value(i) => vec![#to_element],
+                                }
+                            },
+                            Cardinality::RepeatedNillable => quote! {
+                                self.#fname.iter().map(|i| match i {
+                                    Some(i) => #to_element,
+                                    None => #nil,
+                                }).collect()
+                            },
+                        }
+                    })
+                    .collect::<Vec<_>>();
+
+                let attributes_serialize_impl = c
+                    .attributes
+                    .iter()
+                    .map(|(attribute_name, (attributes, attribute_type))| {
+                        let fname = context.field_ident(name, attribute_name);
+                        let aname = Literal::string(attribute_name);
+                        let to_text = gen_to_text(attribute_type);
+                        let ns = match field_namespace(c, attributes) {
+                            Some(ns) => quote! { Some(#ns.to_string()) },
+                            None => quote! { None },
+                        };
+
+                        match (cardinality(attributes, attribute_type), attributes.fixed.as_ref()) {
+                            (Cardinality::Optional, _) => quote! {
+                                self.#fname.as_ref().map(|i| {
+                                    (#ns, #aname.to_string(), #to_text(i))
+                                })
+                            },
+                            (_, Some(fixed)) => quote! {
+                                Some((#ns, #aname.to_string(), #fixed.to_string()))
+                            },
+                            _ => quote! {
+                                Some((
+                                    #ns,
+                                    #aname.to_string(),
+                                    #to_text(&self.#fname),
+                                ))
+                            },
+                        }
+                    })
+                    .collect::<Vec<_>>();
+
+                let to_elements = if fields_serialize_impl.is_empty() {
+                    quote! {
+                        fn to_elements(&self) -> Vec<xmltree::Element> {
+                            vec![]
+                        }
+                    }
+                } else {
+                    quote! {
+                        fn to_elements(&self) -> Vec<xmltree::Element> {
+                            let fields: Vec<Vec<xmltree::Element>> = vec![#(#fields_serialize_impl),*];
+                            fields.into_iter().flatten().collect()
+                        }
+                    }
+                };
+                let to_attributes = if attributes_serialize_impl.is_empty() {
+                    quote! {}
+                } else {
+                    quote! {
+                        fn to_attributes(&self) -> Vec<(Option<String>, String, String)> {
+                            vec![#(#attributes_serialize_impl),*].into_iter().flatten().collect()
+                        }
+                    }
+                };
+                let serialize_impl = quote! {
+                    impl savon::gen::ToElements for #type_name {
+                        #to_elements
+
+                        #to_attributes
+                    }
+                };
+
+                let fields_deserialize_impl = c
+                    .fields
+                    .iter()
+                    .map(|(field_name, (attributes, field_type))| {
+                        let fname = context.field_ident(name, field_name);
+                        let ftype = Literal::string(field_name);
+                        let ns = match field_namespace(c, attributes) {
+                            Some(ns) => quote! { Some(#ns) },
+                            None => quote! { None },
+                        };
+                        let parse =
+                            gen_parse_value(context, field_type, context.is_boxed(name, field_name));
+
+                        let find_child = quote! {
+                            element.find_child(#ftype, #ns)
+                        };
+                        let required_child = quote! {
+                            #find_child
+                                .ok_or_else(|| savon::rpser::xml::Error::NotFoundAtPath {
+                                    path: vec![#ftype.to_string()],
+                                })
+                                .map_err(savon::Error::from)
+                        };
+                        // only absence and xsi:nil are mapped to `None`, parse errors are kept
+                        let parse_nillable = quote! {
+                            |e: &xmltree::Element| if e.is_nil() {
+                                Ok(None)
+                            } else {
+                                parse(e).map(Some)
+                            }
+                        };
+
+                        match cardinality(attributes, field_type) {
+                            Cardinality::Required => quote! {
+                                #fname: #required_child.and_then(#parse)?,
+                            },
+                            Cardinality::Defaulted => {
+                                let value = schema_default(attributes, field_type).expect("defaulted fields have a value");
+                                let default = gen_parse_text(context, field_name, value, field_type);
+                                let check_fixed = match attributes.fixed.as_ref() {
+                                    Some(fixed) => quote! {
+                                        savon::rpser::xml::check_fixed(#ftype, &e.get_text().unwrap_or_default(), #fixed)?;
+                                    },
+                                    None => quote! {},
+                                };
+
+                                quote! {
+                                    #fname: {
+                                        let parse = #parse;
+                                        match #find_child {
+                                            Some(e) => {
+                                                #check_fixed
+                                                parse(e)?
+                                            }
+                                            None => #default?,
+                                        }
+                                    },
+                                }
+                            }
+                            Cardinality::Optional => quote! {
+                                #fname: #find_child.map(#parse).transpose()?,
+                            },
+                            Cardinality::Nillable => quote! {
+                                #fname: {
+                                    let parse = #parse;
+                                    #required_child.and_then(#parse_nillable)?
+                                },
+                            },
+                            Cardinality::OptionalNillable => quote! {
+                                #fname: {
+                                    let parse = #parse;
+                                    match #find_child {
+                                        None => savon::gen::Nillable::Absent,
+                                        Some(e) if e.is_nil() => savon::gen::Nillable::Nil,
+                                        Some(e) => savon::gen::Nillable::Value(parse(e)?),
+                                    }
+                                },
+                            },
+                            Cardinality::Repeated | Cardinality::RepeatedNillable => {
+                                let min = attributes.min_count();
+                                let max = match attributes.max_count() {
+                                    Some(max) => quote! { Some(#max) },
+                                    None => quote! { None },
+                                };
+                                let parse_item = match cardinality(attributes, field_type) {
+                                    Cardinality::RepeatedNillable => parse_nillable,
+                                    _ => quote! { parse },
+                                };
+
+                                quote! {
+                                    #fname: {
+                                        let parse = #parse;
+                                        let children = element.find_children(#ftype, #ns);
+                                        savon::rpser::xml::check_occurences(#ftype, children.len(), #min, #max)?;
+                                        children
+                                            .into_iter()
+                                            .map(#parse_item)
+                                            .collect::<Result<Vec<_>, savon::Error>>()?
+                                    },
+                                }
+                            }
+                        }
+                    })
+                    .collect::<Vec<_>>();
+
+                // parsed attributes are only known by their local name
+                let attributes_deserialize_impl = c
+                    .attributes
+                    .iter()
+                    .map(|(attribute_name, (attributes, attribute_type))| {
+                        let fname = context.field_ident(name, attribute_name);
+                        let aname = Literal::string(attribute_name);
+                        let from_text = gen_from_text(context, attribute_type);
+                        let parse = quote! {
+                            |s: &String| #from_text(#aname, s).map_err(savon::Error::from)
+                        };
+                        let get = quote! { element.attributes.get(#aname) };
+
+                        match cardinality(attributes, attribute_type) {
+                            Cardinality::Optional => quote! {
+                                #fname: #get.map(#parse).transpose()?,
+                            },
+                            Cardinality::Defaulted => {
+                                let value = schema_default(attributes, attribute_type).expect("defaulted fields have a value");
+                                let default = gen_parse_text(context, attribute_name, value, attribute_type);
+                                let check_fixed = match attributes.fixed.as_ref() {
+                                    Some(fixed) => quote! {
+                                        savon::rpser::xml::check_fixed(#aname, s, #fixed)?;
+                                    },
+                                    None => quote! {},
+                                };
+
+                                quote! {
+                                    #fname: {
+                                        let parse = #parse;
+                                        match #get {
+                                            Some(s) => {
+                                                #check_fixed
+                                                parse(s)?
+                                            }
+                                            None => #default?,
+                                        }
+                                    },
+                                }
+                            }
+                            _ => quote! {
+                                #fname: #get
+                                    .ok_or_else(|| savon::rpser::xml::Error::AttributeNotFound {
+                                        name: #aname.to_string(),
+                                    })
+                                    .map_err(savon::Error::from)
+                                    .and_then(#parse)?,
+                            },
+                        }
+                    })
+                    .collect::<Vec<_>>();
+
+                let deserialize_impl = if fields_deserialize_impl.is_empty() && attributes_deserialize_impl.is_empty() {
+                    quote! {
+                        impl savon::gen::FromElement for #type_name {
+                            fn from_element(_element: &xmltree::Element) -> Result<Self, savon::Error> {
+                                Ok(#type_name { })
+                            }
+                        }
+                    }
+                } else {
+                    quote! {
+                        impl savon::gen::FromElement for #type_name {
+                            fn from_element(element: &xmltree::Element) -> Result<Self, savon::Error> {
+                                Ok(#type_name {
+                                    #(#fields_deserialize_impl)*
+                                    #(#attributes_deserialize_impl)*
+                                })
+                            }
+                        }
+                    }
+                };
+
+                // schema defaults replace the Rust zero values
+                let defaults = c
+                    .fields
+                    .iter()
+                    .chain(c.attributes.iter())
+                    .map(|(field_name, (attributes, field_type))| {
+                        let fname = context.field_ident(name, field_name);
+                        match (cardinality(attributes, field_type), schema_default(attributes, field_type)) {
+                            (Cardinality::Required, Some(value)) | (Cardinality::Defaulted, Some(value)) => {
+                                let default = gen_parse_text(context, field_name, value, field_type);
+                                Some(quote! { #fname: #default.expect("default is validated"), })
+                            }
+                            _ => None,
+                        }
+                        .ok_or_else(|| quote! { #fname: Default::default(), })
+                    })
+                    .collect::<Vec<_>>();
+
+                let (derive, default_impl) = if !context.has_default(name) {
+                    // the default value would be infinite
+                    (context.derive_attr(quote! { Clone, Debug }), quote! {})
+                } else if defaults.iter().all(|d| d.is_err()) {
+                    (context.derive_attr(quote! { Clone, Debug, Default }), quote! {})
+                } else {
+                    let defaults = defaults.into_iter().map(|d| d.unwrap_or_else(|d| d));
+                    (
+                        context.derive_attr(quote! { Clone, Debug }),
+                        quote! {
+                            impl Default for #type_name {
+                                fn default() -> Self {
+                                    #type_name {
+                                        #(#defaults)*
+                                    }
+                                }
+                            }
+                        },
+                    )
+                };
+
+                quote! {
+                    #doc
+                    #derive
+                    #vis struct #type_name {
+                        #(#fields)*
+                    }
+
+                    #default_impl
+
+                    #serialize_impl
+
+                    #deserialize_impl
+                }
+            }).chain(simple_types).collect::<Vec<_>>())
+}
+
+/// Newtype for a global element of a built-in type, like
+/// `<element name="Locale" type="xs:string"/>`.
+fn gen_simple_type(context: &Context, name: &str, simple_type: &SimpleType) -> TokenStream {
+    let type_name = context.type_ident(name);
+    let vis = context.vis();
+    let default_doc = format!("`{}` element.", name);
+    let doc = context.doc(Some(&default_doc));
+    let derive = context.derive_attr(quote! { Clone, Debug, Default });
+    let ty = gen_base_type(context, simple_type, &quote! {});
+    let parse = gen_parse_value(context, simple_type, false);
+
+    quote! {
+        #doc
+        #derive
+        #vis struct #type_name(#vis #ty);
+
+        impl savon::gen::FromElement for #type_name {
+            fn from_element(element: &xmltree::Element) -> Result<Self, savon::Error> {
+                let parse = #parse;
+                parse(element).map(#type_name)
+            }
+        }
+    }
+}
+
+/// Check of an `element` against the global element of a message part, which is always
+/// in its schema's namespace. `None` when the element has no parsed type.
+fn part_matches(types: &HashMap<String, Type>, part_element: &str) -> Option<TokenStream> {
+    let namespace = match types.get(part_element)? {
+        Type::Complex(c) => c.namespace.as_ref(),
+        Type::Simple(_) => return None,
+    };
+    let ename = Literal::string(part_element);
+    let ns = match namespace {
+        Some(ns) => quote! { Some(#ns) },
+        None => quote! { None },
+    };
+    Some(quote! { element.matches(#ename, #ns) })
+}
+
+fn gen_messages(
+    context: &Context,
+    messages: &HashMap<String, Message>,
+    types: &HashMap<String, Type>,
+) -> Result<Vec<TokenStream>, GenError> {
+    Ok(sorted(messages)
+        .into_iter()
+        .map(|(message_name, message)| {
+            let mname = context.message_ident(message_name);
+            let iname = context.type_ident(&message.part_element);
+            let vis = context.vis();
+            let default_doc = format!("`{}` message.", message_name);
+            let doc = context.doc(message.documentation.as_deref().or(Some(&default_doc)));
+            let derive = if context.has_default(&message.part_element) {
+                context.derive_attr(quote! { Clone, Debug, Default })
+            } else {
+                context.derive_attr(quote! { Clone, Debug })
+            };
+
+            let check_element = match part_matches(types, &message.part_element) {
+                Some(matches) => {
+                    let ename = Literal::string(&message.part_element);
+                    quote! {
+                        if !#matches {
+                            return Err(savon::rpser::xml::Error::UnexpectedElement {
+                                name: element.name.clone(),
+                                namespace: element.namespace.clone(),
+                                expected: #ename.to_string(),
+                            }.into());
+                        }
+                    }
+                }
+                None => quote! {},
+            };
+
+            // the text of built-in types can't be sent as the children of the body
+            let to_elements = match types.get(&message.part_element) {
+                Some(Type::Simple(_)) => quote! {},
+                _ => quote! {
+                    impl savon::gen::ToElements for #mname {
+                        fn to_elements(&self) -> Vec<xmltree::Element> {
+                            self.0.to_elements()
+                        }
+
+                        fn to_attributes(&self) -> Vec<(Option<String>, String, String)> {
+                            self.0.to_attributes()
+                        }
+                    }
+                },
+            };
+
+            quote! {
+                #doc
+                #derive
+                #vis struct #mname(#vis types::#iname);
+
+                #to_elements
+
+                impl savon::gen::FromElement for #mname {
+                    fn from_element(element: &xmltree::Element) -> Result<Self, savon::Error> {
+                        #check_element
+                        types::#iname::from_element(element).map(#mname)
+                    }
+                }
+            }
+        })
+        .collect::<Vec<_>>())
+}
+
+fn gen_operation_faults(
+    context: &Context,
+    operations: &HashMap<String, Operation>,
+    messages: &HashMap<String, Message>,
+    types: &HashMap<String, Type>,
+) -> Result<Vec<TokenStream>, GenError> {
+    Ok(sorted(operations)
+        .into_iter()
+        .map(|(name, operation)| {
+            let op_error = context.fault_ident(name);
+            let vis = context.vis();
+            let doc = context.doc(Some(&format!("Faults of the `{}` operation.", name)));
+            // no variant to default to
+            let derive = context.derive_attr(quote! { Clone, Debug });
+            operation
+                .faults
+                .as_ref()
+                .map(|faults| {
+                    let variants = faults
+                        .iter()
+                        .map(|fault| {
+                            let fault_name = context.message_ident(fault);
+
+                            quote! {
+                                #fault_name(#fault_name),
+                            }
+                        })
+                        .collect::<Vec<_>>();
+
+                    // the detail holds the part element of one of the fault messages
+                    let mut elements = HashSet::new();
+                    let decode = faults
+                        .iter()
+                        .filter_map(|fault| messages.get(fault).map(|m| (fault, m)))
+                        .filter(|(_, message)| elements.insert(&message.part_element))
+                        .map(|(fault, message)| {
+                            let fault_name = context.message_ident(fault);
+                            let matches =
+                                part_matches(types, &message.part_element).unwrap_or_else(|| {
+                                    let ename = Literal::string(&message.part_element);
+                                    quote! { element.name == #ename }
+                                });
+                            quote! {
+                                if #matches {
+                                    Some(
+                                        <#fault_name as savon::gen::FromElement>::from_element(element)
+                                            .map(#op_error::#fault_name),
+                                    )
+                                } else
+                            }
+                        })
+                        .collect::<Vec<_>>();
+
+                    quote! {
+                        #doc
+                        #derive
+                        #vis enum #op_error {
+                            #(#variants)*
+                        }
+
+                        impl savon::gen::FromFault for #op_error {
+                            fn from_fault(
+                                detail: &xmltree::Element,
+                            ) -> Option<Result<Self, savon::Error>> {
+                                detail
+                                    .children
+                                    .iter()
+                                    .filter_map(|c| c.as_element())
+                                    .find_map(|element| #(#decode)* { None })
+                            }
+                        }
+                    }
+                })
+                .unwrap_or_default()
+        })
+        .collect::<Vec<_>>())
+}
+
+impl Builder {
+    /// Generated code, as tokens.
+    pub fn tokens(&self, wsdl: &Wsdl) -> Result<TokenStream, GenError> {
+        self.validate()?;
+        Context::new(self, wsdl).gen_tokens(wsdl)
+    }
+
+    /// Generated code as a string, pretty-printed when the `pretty` feature is enabled.
+    pub fn generate(&self, wsdl: &Wsdl) -> Result<String, GenError> {
+        let tokens = self.tokens(wsdl)?;
+
+        #[cfg(feature = "pretty")]
+        {
+            let file = syn::parse2::<syn::File>(tokens)?;
+            Ok(prettyplease::unparse(&file))
+        }
+        #[cfg(not(feature = "pretty"))]
+        Ok(tokens.to_string())
+    }
+
+    /// Generates code for the WSDL file at `path`, into a file of the `out` directory
+    /// named after the WSDL file unless [`Builder::file_name`] is set.
+    pub fn write(&self, path: &str, out: &str) -> Result<(), GenError> {
+        let v = std::fs::read(path)?;
+        let wsdl = parse(&v[..])?;
+        // nothing is written unless generation succeeds
+        let generated = self.generate(&wsdl)?;
+
+        let out_path = match self.file_name.as_ref() {
+            Some(file_name) => format!("{}/{}", out, file_name),
+            None => {
+                let file_name = std::path::Path::new(path).file_name().ok_or_else(|| {
+                    std::io::Error::new(
+                        std::io::ErrorKind::InvalidInput,
+                        format!("{} has no file name", path),
+                    )
+                })?;
+                match file_name.to_str() {
+                    Some(n) => match n.rfind('.') {
+                        Some(index) => format!("{}/{}.rs", out, &n[..index].to_snake()),
+                        None => format!("{}/{}.rs", out, n.to_snake()),
+                    },
+                    None => format!("{}/example.rs", out),
+                }
+            }
+        };
+        let mut output = File::create(out_path)?;
+        output.write_all(generated.as_bytes())?;
+        output.flush()?;
+
+        Ok(())
+    }
+
+    fn validate(&self) -> Result<(), GenError> {
+        fn parse_option(name: &'static str, value: &str) -> Result<TokenStream, GenError> {
+            value.parse().map_err(|_| GenError::InvalidOption {
+                name,
+                value: value.to_string(),
+            })
+        }
+        fn check_ident(name: &'static str, value: &str) -> Result<(), GenError> {
+            let tokens = parse_option(name, value)?.into_iter().collect::<Vec<_>>();
+            match tokens.as_slice() {
+                [TokenTree::Ident(_)] => Ok(()),
+                _ => Err(GenError::InvalidOption {
+                    name,
+                    value: value.to_string(),
+                }),
+            }
+        }
+
+        if let Some(module) = self.module.as_ref() {
+            check_ident("module", module)?;
+        }
+        for derive in self.derives.iter() {
+            parse_option("derive", derive)?;
+        }
+        for type_name in self.type_names.values() {
+            check_ident("type_name", type_name)?;
+        }
+        for field_name in self.field_names.values() {
+            check_ident("field_name", field_name)?;
+        }
+        parse_option("visibility", &self.visibility)?;
+
+        Ok(())
+    }
+}
+
+/// Options of a [`Builder`], with the names and properties resolved for one WSDL.
+struct Context<'a> {
+    builder: &'a Builder,
+    type_names: HashMap<String, String>,
+    field_names: HashMap<(String, String), String>,
+    message_names: HashMap<String, String>,
+    operation_names: HashMap<String, String>,
+    /// `_unwrapped` methods, by operation.
+    unwrapped_names: HashMap<String, String>,
+    /// `_with_attachments` methods, by operation.
+    attachments_names: HashMap<String, String>,
+    /// Fault enums, by operation.
+    fault_names: HashMap<String, String>,
+    boxed_fields: HashSet<(String, String)>,
+    /// Types without a `Default` implementation.
+    no_default: HashSet<String>,
+}
+
+impl<'a> Context<'a> {
+    /// Name every type, field, message and operation of the WSDL, so that XML names
+    /// mapping to the same Rust name get distinct ones.
+    fn new(builder: &'a Builder, wsdl: &Wsdl) -> Self {
+        let mut context = Context {
+            builder,
+            type_names: builder.type_names.clone(),
+            field_names: builder.field_names.clone(),
+            message_names: HashMap::new(),
+            operation_names: HashMap::new(),
+            unwrapped_names: HashMap::new(),
+            attachments_names: HashMap::new(),
+            fault_names: HashMap::new(),
+            boxed_fields: recursive_fields(&wsdl.types),
+            no_default: without_default(&wsdl.types),
+        };
+
+        let reserved = RESERVED_TYPE_NAMES.iter().map(|n| n.to_string());
+        let mut used = reserved
+            .clone()
+            .chain(context.type_names.values().cloned())
+            .collect::<HashSet<_>>();
+        for (name, _) in sorted(&wsdl.types) {
+            context
+                .type_names
+                .entry(name.clone())
+                .or_insert_with(|| unique_name(rust_name(name, Case::Camel), "", &mut used));
+        }
+
+        for (type_name, t) in sorted(&wsdl.types) {
+            let c = match t {
+                Type::Complex(c) => c,
+                Type::Simple(_) => continue,
+            };
+            let mut used = context
+                .field_names
+                .iter()
+                .filter(|((t, _), _)| t == type_name)
+                .map(|(_, rust)| rust.clone())
+                .collect::<HashSet<_>>();
+            for (field_name, _) in c.fields.iter().chain(c.attributes.iter()) {
+                context
+                    .field_names
+                    .entry((type_name.clone(), field_name.clone()))
+                    .or_insert_with(|| {
+                        unique_name(rust_name(field_name, Case::Snake), "_", &mut used)
+                    });
+            }
+        }
+
+        // fault enums live with the messages
+        let mut used = reserved.collect::<HashSet<_>>();
+        for (name, _) in sorted(&wsdl.messages) {
+            let rust = unique_name(rust_name(name, Case::Camel), "", &mut used);
+            context.message_names.insert(name.clone(), rust);
+        }
+        for (name, _) in sorted(&wsdl.operations) {
+            let rust = format!(
+                "{}Error",
+                rust_name(name, Case::Camel).trim_start_matches("r#")
+            );
+            let rust = unique_name(rust, "", &mut used);
+            context.fault_names.insert(name.clone(), rust);
+        }
+
+        // the client's own methods, then the operations' and their variants
+        let mut used = ["new", "with_client", "with_transport", "with_auth"]
+            .iter()
+            .map(|n| n.to_string())
+            .collect::<HashSet<_>>();
+        for (name, _) in sorted(&wsdl.operations) {
+            let rust = unique_name(rust_name(name, Case::Snake), "_", &mut used);
+            context.operation_names.insert(name.clone(), rust);
+        }
+        for (name, _) in sorted(&wsdl.operations) {
+            let operation = context.operation_names[name].trim_start_matches("r#");
+            let unwrapped = unique_name(format!("{}_unwrapped", operation), "_", &mut used);
+            let attachments =
+                unique_name(format!("{}_with_attachments", operation), "_", &mut used);
+            context.unwrapped_names.insert(name.clone(), unwrapped);
+            context.attachments_names.insert(name.clone(), attachments);
+        }
+
+        context
+    }
+
+    fn gen_tokens(&self, wsdl: &Wsdl) -> Result<TokenStream, GenError> {
+        let types = gen_types(self, &wsdl.types)?;
+        let messages = gen_messages(self, &wsdl.messages, &wsdl.types)?;
+        let operation_faults =
+            gen_operation_faults(self, &wsdl.operations, &wsdl.messages, &wsdl.types)?;
+        let vis = self.vis();
+
+        let client = if self.builder.client {
+            self.gen_client(wsdl, Calls::Async)?
+        } else {
+            quote! {}
+        };
+        let blocking_client = if self.builder.blocking {
+            let client = self.gen_client(wsdl, Calls::Blocking)?;
+            quote! {
+                #vis mod blocking {
+                    #[allow(unused_imports)]
+                    use super::{messages, types};
+
+                    #client
+                }
+            }
+        } else {
+            quote! {}
+        };
+
+        let tokens = quote! {
+            #vis mod types {
+                #[allow(unused_imports)]
+                use savon::{internal::xmltree, rpser::xml::*};
+
+                #(#types)*
+            }
+
+            #vis mod messages {
+                #[allow(unused_imports)]
+                use {
+                    savon::{internal::xmltree, rpser::xml::*},
+                    super::types,
+                };
+
+                #[allow(unused_imports)]
+                pub(crate) use savon::literal::{LiteralRequest, LiteralResponse};
+
+                #(#messages)*
+
+                #(#operation_faults)*
+            }
+
+            #client
+
+            #blocking_client
+        };
+
+        Ok(match self.builder.module.as_ref() {
+            Some(module) => {
+                let module = to_ident(module);
+                quote! {
+                    #vis mod #module {
+                        #tokens
+                    }
+                }
+            }
+            None => tokens,
+        })
+    }
+
+    /// Service client, with `async` or blocking methods.
+    fn gen_client(&self, wsdl: &Wsdl, calls: Calls) -> Result<TokenStream, GenError> {
+        let target_namespace = Literal::string(&wsdl.target_namespace);
+        let operations = gen_operations(
+            self,
+            &wsdl.operations,
+            &wsdl.messages,
+            &wsdl.types,
+            &target_namespace,
+            wsdl.addressing,
+            calls,
+        )?;
+        let vis = self.vis();
+
+        let service_name = to_ident(&rust_name(&wsdl.name, Case::Camel));
+        let default_doc = match calls {
+            Calls::Async => format!("Client for the `{}` service.", wsdl.name),
+            Calls::Blocking => format!("Blocking client for the `{}` service.", wsdl.name),
+        };
+        let doc = self.doc(wsdl.documentation.as_deref().or(Some(&default_doc)));
+        let auth_doc = self.doc(Some("Authenticate every request."));
+        let transport_doc = self.doc(Some(
+            "Send requests through another transport, like a proxy or `savon::transport::MockTransport`.",
+        ));
+        let reqwest_client = calls.reqwest_client();
+        let constructors = quote! {
+            #[allow(dead_code)]
+            impl #service_name {
+                #vis fn new(base_url: String) -> Self {
+                    Self::with_client(base_url, #reqwest_client::new())
+                }
+
+                #vis fn with_client(base_url: String, client: #reqwest_client) -> Self {
+                    Self::with_transport(base_url, std::sync::Arc::new(client))
+                }
+            }
+        };
+        // generated code can't check savon's features itself
+        let constructors = match calls {
+            Calls::Async => constructors,
+            Calls::Blocking => quote! {
+                savon::blocking::with_reqwest! {
+                    #constructors
+                }
+            },
+        };
+
+        Ok(quote! {
+            #doc
+            #vis struct #service_name {
+                #vis base_url: String,
+                #vis transport: std::sync::Arc<dyn savon::transport::Transport>,
+                #vis options: savon::http::Options,
+            }
+
+            #constructors
+
+            #[allow(dead_code)]
+            impl #service_name {
+                #transport_doc
+                #vis fn with_transport(base_url: String, transport: std::sync::Arc<dyn savon::transport::Transport>) -> Self {
+                    #service_name {
+                        base_url,
+                        transport,
+                        options: Default::default(),
+                    }
+                }
+
+                #auth_doc
+                #vis fn with_auth(mut self, auth: savon::auth::Auth) -> Self {
+                    self.options.auth = Some(auth);
+                    self
+                }
+
+                #(#operations)*
+            }
+        })
+    }
+
+    fn type_ident(&self, name: &str) -> Ident {
+        match self.type_names.get(name) {
+            Some(rust_name) => to_ident(rust_name),
+            None => to_ident(&rust_name(name, Case::Camel)),
+        }
+    }
+
+    fn field_ident(&self, type_name: &str, field_name: &str) -> Ident {
+        match self
+            .field_names
+            .get(&(type_name.to_string(), field_name.to_string()))
+        {
+            Some(rust_name) => to_ident(rust_name),
+            None => to_ident(&rust_name(field_name, Case::Snake)),
+        }
+    }
+
+    fn has_default(&self, type_name: &str) -> bool {
+        !self.no_default.contains(type_name)
+    }
+
+    fn is_boxed(&self, type_name: &str, field_name: &str) -> bool {
+        self.boxed_fields
+            .contains(&(type_name.to_string(), field_name.to_string()))
+    }
+
+    fn message_ident(&self, name: &str) -> Ident {
+        match self.message_names.get(name) {
+            Some(rust_name) => to_ident(rust_name),
+            None => to_ident(&rust_name(name, Case::Camel)),
+        }
+    }
+
+    fn operation_ident(&self, name: &str) -> Ident {
+        match self.operation_names.get(name) {
+            Some(rust_name) => to_ident(rust_name),
+            None => to_ident(&rust_name(name, Case::Snake)),
+        }
+    }
+
+    fn unwrapped_ident(&self, name: &str) -> Ident {
+        match self.unwrapped_names.get(name) {
+            Some(rust_name) => to_ident(rust_name),
+            None => format_ident!(
+                "{}_unwrapped",
+                self.operation_ident(name)
+                    .to_string()
+                    .trim_start_matches("r#")
+            ),
+        }
+    }
+
+    fn attachments_ident(&self, name: &str) -> Ident {
+        match self.attachments_names.get(name) {
+            Some(rust_name) => to_ident(rust_name),
+            None => format_ident!(
+                "{}_with_attachments",
+                self.operation_ident(name)
+                    .to_string()
+                    .trim_start_matches("r#")
+            ),
+        }
+    }
+
+    fn fault_ident(&self, name: &str) -> Ident {
+        match self.fault_names.get(name) {
+            Some(rust_name) => to_ident(rust_name),
+            None => format_ident!(
+                "{}Error",
+                rust_name(name, Case::Camel).trim_start_matches("r#")
+            ),
+        }
+    }
+
+    fn vis(&self) -> TokenStream {
+        self.builder
+            .visibility
+            .parse()
+            .expect("visibility is validated")
+    }
+
+    /// Doc attributes, one per line so they render like `///` comments.
+    fn doc(&self, doc: Option<&str>) -> TokenStream {
+        match doc {
+            Some(doc) if self.builder.docs => {
+                let lines = doc.lines().map(|l| format!(" {}", l.trim()));
+                quote! { #(#[doc = #lines])* }
+            }
+            _ => quote! {},
+        }
+    }
+
+    /// `#[derive(...)]` attribute with the configured derives after the `base` ones.
+    fn derive_attr(&self, base: TokenStream) -> TokenStream {
+        let derives = self
+            .builder
+            .derives
+            .iter()
+            .map(|d| d.parse::<TokenStream>().expect("derives are validated"));
+        quote! { #[derive(#base #(, #derives)*)] }
+    }
+}
+
+pub fn gen_tokens(wsdl: &Wsdl) -> Result<TokenStream, GenError> {
+    Builder::new().tokens(wsdl)
+}
+
+/// Generated code as a string, pretty-printed when the `pretty` feature is enabled.
+pub fn gen(wsdl: &Wsdl) -> Result<String, GenError> {
+    Builder::new().generate(wsdl)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_and_generate(bytes: &[u8]) -> Result<String, GenError> {
+        let wsdl = parse(bytes).unwrap();
+        println!("wsdl: {:?}", wsdl);
+        let code = gen_tokens(&wsdl)?.to_string();
+        println!("generated:\n{}", code);
+        Ok(code)
+    }
+
+    #[test]
+    fn generate_example() {
+        parse_and_generate(include_bytes!("../../assets/example.wsdl")).unwrap();
+    }
+
+    #[test]
+    fn generate_wikipedia() {
+        parse_and_generate(include_bytes!("../../assets/wikipedia-example.wsdl")).unwrap();
+    }
+
+    #[test]
+    fn generate_qualified_elements() {
+        let code = parse_and_generate(include_bytes!("../../assets/WHWebService.wsdl")).unwrap();
+        assert!(code.contains(
+            "xmltree :: Element :: node (\"params\") . with_namespace (\"http://tempuri.org/\")"
+        ));
+        assert!(code.contains("element . find_child (\"Result\" , Some (\"http://tempuri.org/\"))"));
+        assert!(code.contains("\"http://tempuri.org/\" , \"WHWebService___WHDCIF\""));
+    }
+
+    #[test]
+    fn generate_wrapped_operations() {
+        // responses are only unwrapped from the element named after the operation
+        let wsdl = include_str!("../../savon-test/names.wsdl").replace(
+            r#"element="tns:PingUnwrappedResponse""#,
+            r#"element="tns:Failure""#,
+        );
+        let code = parse_and_generate(wsdl.as_bytes()).unwrap();
+        assert!(code.contains("fn ping_unwrapped_2 (& self , value : String)"));
+        assert!(!code.contains("fn ping_unwrapped_unwrapped"));
+    }
+
+    #[test]
+    fn generate_type_referencing_elements() {
+        let names = include_str!("../../savon-test/names.wsdl");
+        let start = names.find(r#"<xs:element name="Ping">"#).unwrap();
+        let end = start + names[start..].find("</xs:element>").unwrap() + "</xs:element>".len();
+        let wsdl = names
+            .replace(
+                &names[start..end],
+                r#"<xs:element name="Ping" type="tns:PingType"/>
+      <xs:complexType name="PingType">
+        <xs:sequence>
+          <xs:element name="value" type="xs:string"/>
+        </xs:sequence>
+      </xs:complexType>"#,
+            )
+            .replace(
+                r#"<xs:element name="Failure">"#,
+                r#"<xs:element name="Failure" type="xs:string"/>
+      <xs:element name="Unused">"#,
+            );
+        let code = parse_and_generate(wsdl.as_bytes()).unwrap();
+
+        assert!(code.contains("pub struct Ping { pub value : String , }"));
+        assert!(code.contains("pub async fn ping_unwrapped_2 (& self , value : String)"));
+        assert!(code.contains("pub struct Failure (pub String) ;"));
+
+        let input = wsdl.replace(r#"element="tns:PingUnwrapped""#, r#"element="tns:Failure""#);
+        assert!(matches!(
+            parse_and_generate(input.as_bytes()),
+            Err(GenError::UnsupportedInput { operation, element }) if operation == "PingUnwrapped" && element == "Failure"
+        ));
+    }
+
+    #[test]
+    fn generate_deterministic_output() {
+        let bytes = include_bytes!("../../savon-test/CountryInfoService.wsdl");
+        let first = gen(&parse(&bytes[..]).unwrap()).unwrap();
+        for _ in 0..4 {
+            assert_eq!(gen(&parse(&bytes[..]).unwrap()).unwrap(), first);
+        }
+    }
+
+    #[cfg(feature = "pretty")]
+    #[test]
+    fn generate_pretty_output() {
+        let code = gen(&parse(&include_bytes!("../../assets/example.wsdl")[..]).unwrap()).unwrap();
+        assert!(code.starts_with("pub mod types {\n"));
+    }
+
+    #[test]
+    fn builder_rejects_invalid_names() {
+        let wsdl = parse(&include_bytes!("../../assets/example.wsdl")[..]).unwrap();
+        let result = Builder::new().type_name("Item", "Line Item").tokens(&wsdl);
+        assert!(matches!(
+            result,
+            Err(GenError::InvalidOption {
+                name: "type_name",
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn write_nothing_on_errors() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/../assets/example.wsdl");
+        let out = std::env::temp_dir().join(format!("savon-gen-write-{}", std::process::id()));
+        std::fs::create_dir_all(&out).unwrap();
+        let out_dir = out.to_str().unwrap();
+
+        let result = Builder::new()
+            .type_name("Item", "Line Item")
+            .write(path, out_dir);
+        assert!(matches!(result, Err(GenError::InvalidOption { .. })));
+        assert!(!out.join("example.rs").exists());
+
+        let result = Builder::new().write("..", out_dir);
+        assert!(matches!(result, Err(GenError::Io(_))));
+
+        Builder::new().write(path, out_dir).unwrap();
+        assert!(out.join("example.rs").exists());
+        std::fs::remove_dir_all(&out).unwrap();
+    }
+
+    #[test]
+    fn generate_rejects_invalid_defaults() {
+        let wsdl = include_str!("../../savon-test/features.wsdl")
+            .replace(r#"default="3""#, r#"default="three""#);
+        let wsdl = parse(wsdl.as_bytes()).unwrap();
+        assert!(matches!(
+            gen_tokens(&wsdl),
+            Err(GenError::InvalidDefault { ref field, ref value, .. })
+                if field == "attempts" && value == "three"
+        ));
+    }
+
+    #[test]
+    fn generate_header_parameters() {
+        let wsdl = parse(&include_bytes!("../../savon-test/features.wsdl")[..]).unwrap();
+        let code = gen_tokens(&wsdl).unwrap().to_string();
+        assert!(code.contains("session_header : types :: Session , locale_header : String"));
+        assert!(code.contains(
+            "savon :: gen :: to_text_element (\"Locale\" , Some (\"http://example.com/features\") , \
+             savon :: rpser :: xml :: ToXmlText :: to_xml_text (& locale_header))"
+        ));
+
+        // headers which are not declared in the schema
+        let wsdl = include_str!("../../savon-test/features.wsdl")
+            .replace(r#"element="tns:Locale""#, r#"element="tns:Language""#);
+        let wsdl = parse(wsdl.as_bytes()).unwrap();
+        assert!(matches!(
+            gen_tokens(&wsdl),
+            Err(GenError::UnknownHeader { ref element, .. }) if element == "Language"
+        ));
+    }
+
+    #[test]
+    fn generate_doc_comments() {
+        let wsdl = parse(&include_bytes!("../../savon-test/features.wsdl")[..]).unwrap();
+        let code = gen_tokens(&wsdl).unwrap().to_string();
+
+        assert!(code.contains(
+            "# [doc = \" Line of an order.\"] # [doc = \" Items are identified by name.\"] \
+             # [derive (Clone , Debug , Default)] pub struct Item"
+        ));
+        assert!(code.contains("# [doc = \" Product name.\"] pub name : String"));
+        assert!(code.contains("# [doc = \" Order to place.\"]"));
+        assert!(code.contains("# [doc = \" Places an order.\"] pub async fn place_order"));
+        assert!(code.contains(
+            "# [doc = \" Exercises generated code features.\"] pub struct FeaturesService"
+        ));
+
+        let code = Builder::new()
+            .emit_docs(false)
+            .tokens(&wsdl)
+            .unwrap()
+            .to_string();
+        assert!(!code.contains("# [doc"));
+    }
+
+    #[test]
+    fn generate_fault_enums() {
+        let mut wsdl = parse(&include_bytes!("../../savon-test/features.wsdl")[..]).unwrap();
+        let upload = wsdl.operations.get_mut("Upload").unwrap();
+        upload.faults = Some(vec!["PlaceOrderSoapOut".to_string()]);
+        let code = Builder::new()
+            .derive("PartialEq")
+            .tokens(&wsdl)
+            .unwrap()
+            .to_string();
+        assert!(code.contains(
+            "# [derive (Clone , Debug , PartialEq)] pub enum UploadError { PlaceOrderSoapOut (PlaceOrderSoapOut) , }"
+        ));
+        // faults are found by qualified name, and decoding errors reported
+        assert!(code.contains(
+            "if element . matches (\"PlaceOrderResponse\" , Some (\"http://example.com/features\")) { Some (< PlaceOrderSoapOut as savon :: gen :: FromElement > :: from_element (element) . map (UploadError :: PlaceOrderSoapOut) ,) } else { None }"
+        ));
+    }
+
+    #[test]
+    fn generate_blocking_client() {
+        let wsdl = parse(&include_bytes!("../../savon-test/CountryInfoService.wsdl")[..]).unwrap();
+        let code = Builder::new()
+            .blocking_client(true)
+            .tokens(&wsdl)
+            .unwrap()
+            .to_string();
+        assert!(code.contains("pub async fn currency_name (& self"));
+        let blocking = &code[code.find("pub mod blocking").unwrap()..];
+        assert!(blocking.contains(
+            "pub fn currency_name_unwrapped (& self , s_currency_i_s_o_code : String) \
+             -> Result < Result < String , () > , savon :: Error > { self . currency_name ("
+        ));
+        assert!(blocking.contains(
+            "savon :: blocking :: block_on (savon :: http :: request_response (& * self . transport ,"
+        ));
+        assert!(blocking.contains(
+            "savon :: blocking :: with_reqwest ! { # [allow (dead_code)] impl CountryInfoService { \
+             pub fn new (base_url : String) -> Self { Self :: with_client (base_url , \
+             savon :: internal :: reqwest :: blocking :: Client :: new ()) }"
+        ));
+        assert!(!blocking.contains("async"));
+
+        // instead of the async client
+        let code = Builder::new()
+            .generate_client(false)
+            .blocking_client(true)
+            .tokens(&wsdl)
+            .unwrap()
+            .to_string();
+        assert!(code.contains("pub mod blocking"));
+        assert!(!code.contains("async"));
+    }
+
+    #[test]
+    fn generate_addressing_actions() {
+        let code = parse_and_generate(include_bytes!("../../savon-test/features.wsdl")).unwrap();
+        assert!(code.contains(
+            "action : Some (\"http://example.com/features/PlaceOrder\" . to_string ()) ,"
+        ));
+        assert!(code.contains(
+            "attachments , action : Some (\"http://example.com/features/FeaturesSoap/UploadRequest\" . to_string ()) ,"
+        ));
+
+        let code =
+            parse_and_generate(include_bytes!("../../savon-test/CountryInfoService.wsdl")).unwrap();
+        assert!(!code.contains("action :"));
+    }
+
+    #[test]
+    fn sanitize_names() {
+        assert_eq!(rust_name("type", Case::Snake), "r#type");
+        assert_eq!(rust_name("self", Case::Snake), "self_");
+        assert_eq!(rust_name("Self", Case::Camel), "Self_");
+        assert_eq!(rust_name("1st", Case::Snake), "_1st");
+        assert_eq!(rust_name("order-id", Case::Snake), "order_id");
+        assert_eq!(rust_name("xml.Lang", Case::Camel), "XmlLang");
+        assert_eq!(
+            rust_name("WHWebService___WHDCIF", Case::Snake),
+            "w_h_web_service_w_h_d_c_i_f"
+        );
+
+        let mut used = HashSet::new();
+        assert_eq!(unique_name("r#type".to_string(), "_", &mut used), "r#type");
+        assert_eq!(unique_name("r#type".to_string(), "_", &mut used), "type_2");
+        assert_eq!(unique_name("r#type".to_string(), "_", &mut used), "type_3");
+    }
+
+    #[test]
+    fn box_recursive_fields() {
+        let wsdl = parse(&include_bytes!("../../savon-test/features.wsdl")[..]).unwrap();
+        let boxed = recursive_fields(&wsdl.types);
+        let mut boxed = boxed
+            .iter()
+            .map(|(t, f)| format!("{}.{}", t, f))
+            .collect::<Vec<_>>();
+        boxed.sort();
+        assert_eq!(
+            boxed,
+            [
+                "Owner.root",
+                "TreeNode.left",
+                "TreeNode.owner",
+                "TreeNode.right"
+            ]
+        );
+    }
+
+    #[test]
+    fn omit_infinite_defaults() {
+        let wsdl = parse(&include_bytes!("../../savon-test/features.wsdl")[..]).unwrap();
+        assert!(without_default(&wsdl.types).is_empty());
+
+        // a tree node requiring an owner, which requires a root node
+        let wsdl = include_str!("../../savon-test/features.wsdl").replace(
+            r#"name="owner" type="tns:Owner" minOccurs="0""#,
+            r#"name="owner" type="tns:Owner""#,
+        );
+        let wsdl = parse(wsdl.as_bytes()).unwrap();
+        let mut no_default = without_default(&wsdl.types).into_iter().collect::<Vec<_>>();
+        no_default.sort();
+        assert_eq!(no_default, ["Owner", "TreeNode"]);
+
+        let code = gen_tokens(&wsdl).unwrap().to_string();
+        assert!(code.contains("# [derive (Clone , Debug)] pub struct TreeNode"));
+        assert!(code.contains("# [derive (Clone , Debug)] pub struct Owner"));
+        assert!(code.contains("# [derive (Clone , Debug , Default)] pub struct Item"));
+    }
+}
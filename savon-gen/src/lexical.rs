@@ -0,0 +1,189 @@
+//! Checks of XML Schema lexical values, for the schema defaults and fixed values which the
+//! generated code parses with `savon::rpser::xml::FromXmlText`.
+//!
+//! savon's tests check that both accept the same values.
+
+use crate::wsdl::SimpleType;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime};
+use rust_decimal::Decimal;
+
+/// Check that `text` is a value of a built-in type, or give the reason it is not.
+/// Strings and complex types accept any text.
+pub fn check(simple_type: &SimpleType, text: &str) -> Result<(), String> {
+    let trimmed = text.trim();
+    match simple_type {
+        SimpleType::Boolean => match trimmed {
+            "true" | "1" | "false" | "0" => Ok(()),
+            _ => Err("not a boolean".to_string()),
+        },
+        SimpleType::String | SimpleType::Complex(_) => Ok(()),
+        SimpleType::Float => match trimmed {
+            "INF" | "-INF" => Ok(()),
+            t => t.parse::<f64>().map(drop).map_err(|e| e.to_string()),
+        },
+        SimpleType::Decimal => check_decimal(trimmed),
+        SimpleType::Int => trimmed.parse::<i64>().map(drop).map_err(|e| e.to_string()),
+        SimpleType::DateTime => DateTime::parse_from_rfc3339(trimmed)
+            .map(drop)
+            .or_else(|_| NaiveDateTime::parse_from_str(trimmed, "%Y-%m-%dT%H:%M:%S%.f").map(drop))
+            .map_err(|e| e.to_string()),
+        SimpleType::Date => NaiveDate::parse_from_str(strip_timezone(trimmed), "%Y-%m-%d")
+            .map(drop)
+            .map_err(|e| e.to_string()),
+        SimpleType::Time => NaiveTime::parse_from_str(strip_timezone(trimmed), "%H:%M:%S%.f")
+            .map(drop)
+            .map_err(|e| e.to_string()),
+        SimpleType::Duration => match is_duration(trimmed) {
+            true => Ok(()),
+            false => Err("not a duration".to_string()),
+        },
+        SimpleType::Base64Binary => {
+            let encoded = text
+                .bytes()
+                .filter(|b| !b.is_ascii_whitespace())
+                .collect::<Vec<_>>();
+            STANDARD
+                .decode(encoded)
+                .map(drop)
+                .map_err(|e| e.to_string())
+        }
+        SimpleType::HexBinary => {
+            if trimmed.len() % 2 != 0 {
+                Err("odd number of hex digits".to_string())
+            } else if !trimmed.bytes().all(|b| b.is_ascii_hexdigit()) {
+                Err("invalid hex digit".to_string())
+            } else {
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Exact decimals, in plain or exponent form, which `Decimal` holds without rounding.
+fn check_decimal(text: &str) -> Result<(), String> {
+    let unsigned = text.strip_prefix('+').unwrap_or(text);
+    let plain = match unsigned.contains(['e', 'E']) {
+        true => without_exponent(unsigned),
+        false => Some(unsigned.to_string()),
+    };
+    match plain.and_then(|plain| Decimal::from_str_exact(&plain).ok()) {
+        Some(_) => Ok(()),
+        None => Err("not a decimal, or too precise".to_string()),
+    }
+}
+
+/// Writes a number in exponent form, like `1.5E2`, as a plain decimal, `150`.
+fn without_exponent(text: &str) -> Option<String> {
+    let (mantissa, exponent) = text.split_once(['e', 'E'])?;
+    let exponent = exponent.strip_prefix('+').unwrap_or(exponent);
+    let exponent: i32 = exponent.parse().ok()?;
+    let (negative, mantissa) = match mantissa.strip_prefix('-') {
+        Some(mantissa) => (true, mantissa),
+        None => (false, mantissa),
+    };
+    let (integer, fraction) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+    let digits = format!("{}{}", integer, fraction);
+    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) || exponent.abs() > 64 {
+        return None;
+    }
+
+    let point = integer.len() as i32 + exponent;
+    let plain = if point <= 0 {
+        format!("0.{}{}", "0".repeat(-point as usize), digits)
+    } else if point as usize >= digits.len() {
+        format!("{}{}", digits, "0".repeat(point as usize - digits.len()))
+    } else {
+        format!(
+            "{}.{}",
+            &digits[..point as usize],
+            &digits[point as usize..]
+        )
+    };
+    Some(if negative {
+        format!("-{}", plain)
+    } else {
+        plain
+    })
+}
+
+/// Removes the optional timezone of an `xs:date` or `xs:time`.
+fn strip_timezone(text: &str) -> &str {
+    if let Some(text) = text.strip_suffix('Z') {
+        return text;
+    }
+    match text.len().checked_sub(6).and_then(|i| text.get(i..)) {
+        Some(tz) if tz.starts_with(['+', '-']) && tz.as_bytes()[3] == b':' => {
+            &text[..text.len() - 6]
+        }
+        _ => text,
+    }
+}
+
+/// Whether the text has the `PnYnMnDTnHnMnS` form, with seconds precise to the nanosecond.
+fn is_duration(text: &str) -> bool {
+    let text = text.strip_prefix('-').unwrap_or(text);
+    let text = match text.strip_prefix('P') {
+        Some(text) => text,
+        None => return false,
+    };
+    let (date, time) = match text.find('T') {
+        Some(index) if index + 1 < text.len() => (&text[..index], Some(&text[index + 1..])),
+        Some(_) => return false,
+        None => (text, None),
+    };
+    if date.is_empty() && time.is_none() {
+        return false;
+    }
+
+    // components in order, each designator at most once
+    fn components(text: &str, designators: &[char], seconds: bool) -> bool {
+        let mut rest = text;
+        let mut allowed = designators;
+        while !rest.is_empty() {
+            let index = match rest.find(|c: char| c.is_ascii_alphabetic()) {
+                Some(index) if index > 0 => index,
+                _ => return false,
+            };
+            let designator = rest[index..].chars().next().unwrap_or_default();
+            let position = match allowed.iter().position(|d| *d == designator) {
+                Some(position) => position,
+                None => return false,
+            };
+            let value = &rest[..index];
+            let valid = match (seconds, designator) {
+                (true, 'S') => is_seconds(value),
+                _ => value.parse::<u32>().is_ok(),
+            };
+            if !valid {
+                return false;
+            }
+            allowed = &allowed[position + 1..];
+            rest = &rest[index + 1..];
+        }
+        true
+    }
+
+    components(date, &['Y', 'M', 'D'], false)
+        && components(time.unwrap_or_default(), &['H', 'M', 'S'], true)
+}
+
+/// Seconds of a duration, with a fraction no more precise than the nanosecond.
+fn is_seconds(value: &str) -> bool {
+    let (seconds, fraction) = match value.split_once('.') {
+        Some((seconds, fraction)) => (seconds, Some(fraction)),
+        None => (value, None),
+    };
+    if !seconds.starts_with(|c: char| c.is_ascii_digit()) || seconds.parse::<u32>().is_err() {
+        return false;
+    }
+    match fraction {
+        Some(fraction) => {
+            !fraction.is_empty()
+                && fraction.bytes().all(|b| b.is_ascii_digit())
+                && fraction.bytes().skip(9).all(|b| b == b'0')
+        }
+        None => true,
+    }
+}
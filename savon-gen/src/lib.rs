@@ -0,0 +1,13 @@
+//! WSDL parsing and code generation for savon.
+//!
+//! This crate is used through `savon`, which re-exports its modules as `savon::wsdl` and
+//! `savon::gen`, and by the `savon::wsdl!` macro. Generated code only depends on `savon`.
+#[macro_use]
+extern crate log;
+#[macro_use]
+extern crate quote;
+
+pub mod gen;
+#[doc(hidden)]
+pub mod lexical;
+pub mod wsdl;
@@ -0,0 +1,1232 @@
+//! WSDL inspection helpers.
+
+use std::collections::HashMap;
+use xmltree::Element;
+
+#[derive(Debug)]
+pub enum WsdlError {
+    Parse(xmltree::ParseError),
+    ElementNotFound(&'static str),
+    AttributeNotFound(&'static str),
+    NotAnElement,
+    Empty,
+    /// `minOccurs` or `maxOccurs` is neither a number nor `unbounded`.
+    InvalidOccurence(String),
+    /// Schema definition which is not supported, like a named `simpleType`.
+    UnsupportedType {
+        name: String,
+        kind: String,
+    },
+    /// Global element whose `type` is neither a complex type of the schemas nor a built-in
+    /// type.
+    UnknownType {
+        name: String,
+        type_name: String,
+    },
+}
+
+impl From<xmltree::ParseError> for WsdlError {
+    fn from(error: xmltree::ParseError) -> Self {
+        WsdlError::Parse(error)
+    }
+}
+
+/// WSDL document.
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Wsdl {
+    pub name: String,
+    pub target_namespace: String,
+    pub types: HashMap<String, Type>,
+    pub messages: HashMap<String, Message>,
+    pub operations: HashMap<String, Operation>,
+    pub ports: Vec<Port>,
+    /// Service documentation.
+    pub documentation: Option<String>,
+    /// Whether requests carry WS-Addressing headers, as required by `wsaw:UsingAddressing`,
+    /// a `wsam:Addressing` policy or explicit `wsaw:Action`/`wsam:Action` attributes.
+    pub addressing: bool,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum SimpleType {
+    Boolean,
+    String,
+    Float,
+    /// `xs:decimal`, kept exact: values with more digits than `rust_decimal::Decimal` holds
+    /// are rejected.
+    Decimal,
+    Int,
+    DateTime,
+    Date,
+    Time,
+    Duration,
+    /// `xs:base64Binary`, decoded to bytes.
+    Base64Binary,
+    /// `xs:hexBinary`, decoded to bytes.
+    HexBinary,
+    Complex(String),
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum Occurence {
+    Unbounded,
+    Num(u32),
+}
+
+/// Whether an element must be in its schema's target namespace, from its `form` attribute
+/// or the schema's `elementFormDefault`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum Form {
+    Qualified,
+    #[default]
+    Unqualified,
+}
+
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct TypeAttribute {
+    /// Whether the element may be sent as `xsi:nil="true"`, unrelated to it being optional.
+    pub nillable: bool,
+    pub min_occurs: Option<Occurence>,
+    pub max_occurs: Option<Occurence>,
+    pub form: Form,
+    /// Value used when the element or attribute is absent.
+    pub default: Option<String>,
+    /// Only value the element or attribute may have.
+    pub fixed: Option<String>,
+    /// Text of the element's or attribute's `xs:annotation`.
+    pub documentation: Option<String>,
+}
+
+impl TypeAttribute {
+    /// Whether the element may appear more than once.
+    pub fn is_repeated(&self) -> bool {
+        match self.max_occurs {
+            Some(Occurence::Unbounded) => true,
+            Some(Occurence::Num(n)) => n > 1,
+            None => false,
+        }
+    }
+
+    /// Whether the element may be absent (`minOccurs="0"`).
+    pub fn is_optional(&self) -> bool {
+        self.min_count() == 0
+    }
+
+    /// Minimum number of occurences, 1 when unspecified.
+    pub fn min_count(&self) -> usize {
+        match self.min_occurs {
+            Some(Occurence::Num(n)) => n as usize,
+            _ => 1,
+        }
+    }
+
+    /// Maximum number of occurences, `None` when unbounded.
+    pub fn max_count(&self) -> Option<usize> {
+        match self.max_occurs {
+            Some(Occurence::Num(n)) => Some(n as usize),
+            Some(Occurence::Unbounded) => None,
+            None => Some(1),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ComplexType {
+    /// Target namespace of the schema defining this type.
+    pub namespace: Option<String>,
+    /// Fields in schema order, as `xs:sequence` requires them to be serialized in that order.
+    pub fields: Vec<(String, (TypeAttribute, SimpleType))>,
+    /// XML attributes, required ones having a `min_occurs` of 1.
+    pub attributes: Vec<(String, (TypeAttribute, SimpleType))>,
+    /// Text of the `xs:annotation` of the element or complex type.
+    pub documentation: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum Type {
+    Simple(SimpleType),
+    Complex(ComplexType),
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Message {
+    pub part_name: String,
+    pub part_element: String,
+    pub documentation: Option<String>,
+}
+
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Operation {
+    pub name: String,
+    pub input: Option<String>,
+    pub output: Option<String>,
+    pub faults: Option<Vec<String>>,
+    pub documentation: Option<String>,
+    /// Parts of the input sent as attachments, from a `mime:multipartRelated` binding.
+    pub input_attachments: Vec<MimePart>,
+    /// Parts of the output received as attachments.
+    pub output_attachments: Vec<MimePart>,
+    /// Headers of the input, from `soap:header` bindings.
+    pub input_headers: Vec<SoapHeader>,
+    /// WS-Addressing action of the input, from `wsaw:Action` or `wsam:Action`, or the
+    /// default one derived from the port type.
+    pub input_action: Option<String>,
+    /// WS-Addressing action of the output.
+    pub output_action: Option<String>,
+}
+
+/// Message part bound to a SOAP header.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct SoapHeader {
+    pub message: String,
+    pub part: String,
+    /// Element of the part.
+    pub element: String,
+    /// Type of the element, when it is a built-in simple type rather than a complex type.
+    pub simple_type: Option<SimpleType>,
+    /// Namespace of the element, when it has a simple type.
+    pub namespace: Option<String>,
+}
+
+/// Message part bound to a MIME part, for SOAP with Attachments.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct MimePart {
+    pub part: String,
+    /// Allowed content types, from `mime:content` alternatives.
+    pub content_types: Vec<String>,
+}
+
+/// Service port (WSDL 1.1) or endpoint (WSDL 2.0).
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Port {
+    pub name: String,
+    pub binding: String,
+    /// Address from the `soap:address` extension, or the endpoint's `address`.
+    pub location: Option<String>,
+}
+
+//FIXME: splitting the namespace is the naive way, we should keep the namespace
+// and check for collisions instead
+fn split_namespace(s: &str) -> &str {
+    match s.find(':') {
+        None => s,
+        Some(index) => &s[index + 1..],
+    }
+}
+
+fn parse_form(s: Option<&String>) -> Option<Form> {
+    match s.map(|s| s.as_str()) {
+        Some("qualified") => Some(Form::Qualified),
+        Some("unqualified") => Some(Form::Unqualified),
+        _ => None,
+    }
+}
+
+/// Text of a WSDL `documentation` child, or of an XSD `annotation/documentation` one.
+fn parse_documentation(el: &Element) -> Option<String> {
+    let documentation = el.get_child("documentation").or_else(|| {
+        el.get_child("annotation")
+            .and_then(|a| a.get_child("documentation"))
+    })?;
+    let text = documentation.get_text()?;
+    let text = text.trim();
+    if text.is_empty() {
+        None
+    } else {
+        Some(text.to_string())
+    }
+}
+
+fn parse_simple_type(s: &str) -> SimpleType {
+    match split_namespace(s) {
+        "boolean" => SimpleType::Boolean,
+        "string" => SimpleType::String,
+        "int" => SimpleType::Int,
+        "float" | "double" => SimpleType::Float,
+        "decimal" => SimpleType::Decimal,
+        "dateTime" => SimpleType::DateTime,
+        "date" => SimpleType::Date,
+        "time" => SimpleType::Time,
+        "duration" => SimpleType::Duration,
+        "base64Binary" => SimpleType::Base64Binary,
+        "hexBinary" => SimpleType::HexBinary,
+        s => SimpleType::Complex(s.to_string()),
+    }
+}
+
+pub fn parse_types(root_el: &Element) -> Result<HashMap<String, Type>, WsdlError> {
+    let mut types = HashMap::new();
+    // elements like <element name="Echo" type="tns:EchoType"/>, resolved once every
+    // schema is read
+    let mut typed_elements = Vec::new();
+
+    let schemas = root_el
+        .get_child("types")
+        .into_iter()
+        .flat_map(|e| e.children.iter().filter_map(|c| c.as_element()))
+        .filter(|c| c.name == "schema");
+
+    for types_el in schemas {
+        let namespace = types_el.attributes.get("targetNamespace").cloned();
+        let element_form_default =
+            parse_form(types_el.attributes.get("elementFormDefault")).unwrap_or_default();
+        let attribute_form_default =
+            parse_form(types_el.attributes.get("attributeFormDefault")).unwrap_or_default();
+
+        for elem in types_el
+            .children
+            .iter()
+            .filter_map(|c| c.as_element())
+            .filter(|e| e.name != "annotation")
+        {
+            trace!("type: {:#?}", elem);
+            let name = elem
+                .attributes
+                .get("name")
+                .ok_or(WsdlError::AttributeNotFound("name"))?;
+
+            // sometimes we have <element name="TypeName"><complexType>...</complexType></element>,
+            // sometimes we have <complexType name="TypeName">...</complexType>
+            //let current_child = elem.children.get(0).ok_or(WsdlError::Empty)?
+            //    .as_element().ok_or(WsdlError::NotAnElement)?;
+
+            let child = if elem.name == "complexType" {
+                elem
+            } else {
+                match elem
+                    .children
+                    .iter()
+                    .filter_map(|c| c.as_element())
+                    .find(|e| e.name != "annotation")
+                {
+                    Some(child) => child,
+                    None if elem.attributes.contains_key("type") => {
+                        typed_elements.push((name, &elem.attributes["type"], elem));
+                        continue;
+                    }
+                    None => {
+                        trace!("element {} has no type definition", name);
+                        types.insert(
+                            name.to_string(),
+                            Type::Complex(ComplexType {
+                                namespace: namespace.clone(),
+                                fields: Vec::new(),
+                                attributes: Vec::new(),
+                                documentation: parse_documentation(elem),
+                            }),
+                        );
+                        continue;
+                    }
+                }
+            };
+            let documentation = parse_documentation(elem).or_else(|| parse_documentation(child));
+
+            if child.name == "complexType" {
+                let is_abstract = child
+                    .attributes
+                    .get("abstract")
+                    .map(|v| v == "true")
+                    .unwrap_or(false);
+                if is_abstract {
+                    types.insert(
+                        name.to_string(),
+                        Type::Complex(ComplexType {
+                            namespace: namespace.clone(),
+                            fields: Vec::new(),
+                            attributes: Vec::new(),
+                            documentation,
+                        }),
+                    );
+                    continue;
+                }
+
+                let field_container_el = child
+                    .children
+                    .iter()
+                    .filter_map(|c| c.as_element())
+                    .find(|e| e.name != "annotation")
+                    .ok_or(WsdlError::Empty)?;
+
+                if field_container_el.name == "complexContent" {
+                    types.insert(
+                        name.to_string(),
+                        Type::Complex(ComplexType {
+                            namespace: namespace.clone(),
+                            fields: Vec::new(),
+                            attributes: Vec::new(),
+                            documentation,
+                        }),
+                    );
+                    continue;
+                }
+
+                let mut attributes = Vec::new();
+                for attribute in child
+                    .children
+                    .iter()
+                    .filter_map(|c| c.as_element())
+                    .filter(|e| e.name == "attribute")
+                {
+                    trace!("attribute: {:#?}", attribute);
+                    let attribute_name = attribute
+                        .attributes
+                        .get("name")
+                        .ok_or(WsdlError::AttributeNotFound("name"))?;
+                    let min_occurs = match attribute.attributes.get("use").map(|s| s.as_str()) {
+                        Some("prohibited") => continue,
+                        Some("required") => Occurence::Num(1),
+                        _ => Occurence::Num(0),
+                    };
+                    // attributes can only have simple types
+                    let attribute_type = match attribute
+                        .attributes
+                        .get("type")
+                        .map(|t| parse_simple_type(t))
+                    {
+                        Some(SimpleType::Complex(_)) | None => SimpleType::String,
+                        Some(t) => t,
+                    };
+
+                    let type_attributes = TypeAttribute {
+                        nillable: false,
+                        min_occurs: Some(min_occurs),
+                        max_occurs: None,
+                        form: parse_form(attribute.attributes.get("form"))
+                            .unwrap_or(attribute_form_default),
+                        default: attribute.attributes.get("default").cloned(),
+                        fixed: attribute.attributes.get("fixed").cloned(),
+                        documentation: parse_documentation(attribute),
+                    };
+                    attributes.push((
+                        attribute_name.to_string(),
+                        (type_attributes, attribute_type),
+                    ));
+                }
+
+                let mut fields = Vec::new();
+                // a lone attribute has no fields
+                let field_elements = if field_container_el.name == "attribute" {
+                    &[][..]
+                } else {
+                    &field_container_el.children[..]
+                };
+                for field in field_elements
+                    .iter()
+                    .filter_map(|c| c.as_element())
+                    .filter(|e| e.name != "annotation")
+                {
+                    trace!("field: {:#?}", field);
+                    let field_name = field
+                        .attributes
+                        .get("name")
+                        .ok_or(WsdlError::AttributeNotFound("name"))?;
+                    let field_type = field
+                        .attributes
+                        .get("type")
+                        .ok_or(WsdlError::AttributeNotFound("type"))?;
+                    let nillable = match field.attributes.get("nillable").map(|s| s.as_str()) {
+                        Some("true") => true,
+                        Some("false") => false,
+                        _ => false,
+                    };
+
+                    let min_occurs = match field.attributes.get("minOccurs").map(|s| s.as_str()) {
+                        None => None,
+                        Some("unbounded") => Some(Occurence::Unbounded),
+                        Some(n) => Some(Occurence::Num(
+                            n.parse()
+                                .map_err(|_| WsdlError::InvalidOccurence(n.to_string()))?,
+                        )),
+                    };
+                    let max_occurs = match field.attributes.get("maxOccurs").map(|s| s.as_str()) {
+                        None => None,
+                        Some("unbounded") => Some(Occurence::Unbounded),
+                        Some(n) => Some(Occurence::Num(
+                            n.parse()
+                                .map_err(|_| WsdlError::InvalidOccurence(n.to_string()))?,
+                        )),
+                    };
+
+                    let form =
+                        parse_form(field.attributes.get("form")).unwrap_or(element_form_default);
+
+                    trace!("field {:?} -> {:?}", field_name, field_type);
+                    let type_attributes = TypeAttribute {
+                        nillable,
+                        min_occurs,
+                        max_occurs,
+                        form,
+                        default: field.attributes.get("default").cloned(),
+                        fixed: field.attributes.get("fixed").cloned(),
+                        documentation: parse_documentation(field),
+                    };
+
+                    let simple_type = parse_simple_type(field_type);
+                    fields.push((field_name.to_string(), (type_attributes, simple_type)));
+                }
+
+                types.insert(
+                    name.to_string(),
+                    Type::Complex(ComplexType {
+                        namespace: namespace.clone(),
+                        fields,
+                        attributes,
+                        documentation,
+                    }),
+                );
+            } else {
+                trace!("child {:#?}", child);
+                return Err(WsdlError::UnsupportedType {
+                    name: name.to_string(),
+                    kind: child.name.clone(),
+                });
+            }
+        }
+    }
+
+    for (name, type_name, elem) in typed_elements {
+        let element_type = match parse_simple_type(type_name) {
+            SimpleType::Complex(complex_name) => match types.get(&complex_name) {
+                Some(Type::Complex(c)) => Type::Complex(ComplexType {
+                    documentation: parse_documentation(elem).or_else(|| c.documentation.clone()),
+                    ..c.clone()
+                }),
+                _ => {
+                    return Err(WsdlError::UnknownType {
+                        name: name.to_string(),
+                        type_name: type_name.to_string(),
+                    })
+                }
+            },
+            simple_type => Type::Simple(simple_type),
+        };
+        types.insert(name.to_string(), element_type);
+    }
+    Ok(types)
+}
+
+pub fn parse_messages(root_el: &Element) -> Result<HashMap<String, Message>, WsdlError> {
+    let mut messages = HashMap::new();
+    for message in root_el
+        .children
+        .iter()
+        .filter_map(|c| c.as_element())
+        .filter(|c| c.name == "message")
+    {
+        trace!("message: {:#?}", message);
+        let name = message
+            .attributes
+            .get("name")
+            .ok_or(WsdlError::AttributeNotFound("name"))?;
+        // parts bound to attachments have a type rather than an element
+        let c = message
+            .children
+            .iter()
+            .filter_map(|c| c.as_element())
+            .find(|c| c.name == "part" && c.attributes.contains_key("element"))
+            .or_else(|| message.get_child("part"))
+            .ok_or(WsdlError::ElementNotFound("part"))?;
+        //FIXME: namespace
+        let part_name = c
+            .attributes
+            .get("name")
+            .ok_or(WsdlError::AttributeNotFound("name"))?
+            .to_string();
+        let part_element = split_namespace(
+            c.attributes
+                .get("element")
+                .ok_or(WsdlError::AttributeNotFound("element"))?,
+        )
+        .to_string();
+
+        messages.insert(
+            name.to_string(),
+            Message {
+                part_name,
+                part_element,
+                documentation: parse_documentation(message),
+            },
+        );
+    }
+    Ok(messages)
+}
+
+pub fn parse_operations(root_el: &Element) -> Result<HashMap<String, Operation>, WsdlError> {
+    let mut operations = HashMap::new();
+    if let Some(port_type_el) = root_el.get_child("portType") {
+        let port_type_name = port_type_el
+            .attributes
+            .get("name")
+            .ok_or(WsdlError::AttributeNotFound("name"))?;
+        for operation in port_type_el
+            .children
+            .iter()
+            .filter_map(|c| c.as_element())
+            .filter(|e| e.name == "operation")
+        {
+            trace!("operation: {:#?}", operation);
+            let operation_name = operation
+                .attributes
+                .get("name")
+                .ok_or(WsdlError::AttributeNotFound("name"))?;
+
+            let mut input = None;
+            let mut output = None;
+            let mut faults = None;
+            let mut input_action = None;
+            let mut output_action = None;
+            for child in operation
+                .children
+                .iter()
+                .filter_map(|c| c.as_element())
+                .filter(|c| c.attributes.contains_key("message"))
+            {
+                // `wsaw:Action` and `wsam:Action`, parsed attributes only keep their local name
+                let action = child.attributes.get("Action").cloned();
+                let message = split_namespace(
+                    child
+                        .attributes
+                        .get("message")
+                        .ok_or(WsdlError::AttributeNotFound("message"))?,
+                );
+                // FIXME: not testing for unicity
+                match child.name.as_str() {
+                    "input" => {
+                        input = Some(message.to_string());
+                        input_action = action;
+                    }
+                    "output" => {
+                        output = Some(message.to_string());
+                        output_action = action;
+                    }
+                    "fault" => {
+                        if faults.is_none() {
+                            faults = Some(Vec::new());
+                        }
+                        if let Some(v) = faults.as_mut() {
+                            v.push(message.to_string());
+                        }
+                    }
+                    _ => return Err(WsdlError::ElementNotFound("operation member")),
+                }
+            }
+
+            let default_action = |direction: &str, suffix: &str| {
+                let name = operation
+                    .get_child(direction)
+                    .and_then(|e| e.attributes.get("name"))
+                    .cloned()
+                    .unwrap_or_else(|| format!("{}{}", operation_name, suffix));
+                default_action(root_el, port_type_name, &name)
+            };
+            let input_suffix = if output.is_some() { "Request" } else { "" };
+            if input.is_some() && input_action.is_none() {
+                input_action = default_action("input", input_suffix);
+            }
+            if output.is_some() && output_action.is_none() {
+                output_action = default_action("output", "Response");
+            }
+
+            operations.insert(
+                operation_name.to_string(),
+                Operation {
+                    name: operation_name.to_string(),
+                    input,
+                    output,
+                    faults,
+                    documentation: parse_documentation(operation),
+                    input_attachments: vec![],
+                    output_attachments: vec![],
+                    input_headers: vec![],
+                    input_action,
+                    output_action,
+                },
+            );
+        }
+    } else {
+        let bind_operator = root_el
+            .get_child("binding")
+            .ok_or(WsdlError::ElementNotFound("binding"))?;
+        for operation in bind_operator
+            .children
+            .iter()
+            .filter_map(|c| c.as_element())
+            .filter(|c| c.name.eq("operation"))
+        {
+            // WSDL 2.0 bindings reference the interface operation instead of naming it
+            let operation_name = operation
+                .attributes
+                .get("name")
+                .or_else(|| operation.attributes.get("ref"))
+                .map(|s| split_namespace(s))
+                .ok_or(WsdlError::AttributeNotFound("name"))?;
+
+            let mut input = None;
+            let mut output = None;
+            let mut faults = None;
+            for child in operation
+                .children
+                .iter()
+                .filter_map(|c| c.as_element())
+                .filter(|c| !c.children.is_empty() && c.name != "documentation")
+            {
+                // FIXME: not testing for unicity
+                match child.name.as_str() {
+                    "input" => input = Some(String::from("LiteralRequest")),
+                    "output" => output = Some(String::from("LiteralResponse")),
+                    "fault" => {
+                        if faults.is_none() {
+                            faults = Some(Vec::new());
+                        }
+                        if let Some(v) = faults.as_mut() {
+                            v.push(String::from("literal"));
+                        }
+                    }
+                    _ => return Err(WsdlError::ElementNotFound("operation member")),
+                }
+            }
+
+            operations.insert(
+                operation_name.to_string(),
+                Operation {
+                    name: operation_name.to_string(),
+                    input,
+                    output,
+                    faults,
+                    documentation: parse_documentation(operation),
+                    input_attachments: vec![],
+                    output_attachments: vec![],
+                    input_headers: vec![],
+                    input_action: None,
+                    output_action: None,
+                },
+            );
+        }
+    }
+
+    for binding in root_el
+        .children
+        .iter()
+        .filter_map(|c| c.as_element())
+        .filter(|c| c.name == "binding")
+    {
+        for binding_operation in binding
+            .children
+            .iter()
+            .filter_map(|c| c.as_element())
+            .filter(|c| c.name == "operation")
+        {
+            let operation = binding_operation
+                .attributes
+                .get("name")
+                .and_then(|name| operations.get_mut(name));
+            if let Some(operation) = operation {
+                if let Some(input) = binding_operation.get_child("input") {
+                    operation.input_attachments = parse_mime_parts(input);
+                    operation.input_headers = parse_soap_headers(root_el, input)?;
+                }
+                if let Some(output) = binding_operation.get_child("output") {
+                    operation.output_attachments = parse_mime_parts(output);
+                }
+            }
+        }
+    }
+
+    Ok(operations)
+}
+
+/// Default WS-Addressing action of a message: `{targetNamespace}/{portType}/{name}`, with
+/// `:` as delimiter for URN namespaces.
+fn default_action(root_el: &Element, port_type: &str, name: &str) -> Option<String> {
+    let namespace = root_el.attributes.get("targetNamespace")?;
+    let delimiter = if namespace.starts_with("urn:") {
+        ':'
+    } else {
+        '/'
+    };
+    let namespace = namespace.strip_suffix(delimiter).unwrap_or(namespace);
+    Some(format!(
+        "{}{}{}{}{}",
+        namespace, delimiter, port_type, delimiter, name
+    ))
+}
+
+/// WS-Addressing WSDL binding namespace, of `wsaw:UsingAddressing`.
+const WSAW_NS: &str = "http://www.w3.org/2006/05/addressing/wsdl";
+/// WS-Addressing metadata namespace, of the `wsam:Addressing` policy assertion.
+const WSAM_NS: &str = "http://www.w3.org/2007/05/addressing/metadata";
+
+/// Whether the document requires WS-Addressing, through `wsaw:UsingAddressing`, a
+/// `wsam:Addressing` policy assertion or explicit actions.
+///
+/// Addressing marked optional, with `wsdl:required="false"` or `wsp:Optional="true"`, is
+/// not used.
+fn uses_addressing(el: &Element) -> bool {
+    let marker = match el.namespace.as_deref() {
+        Some(WSAW_NS) => el.name == "UsingAddressing",
+        Some(WSAM_NS) => el.name == "Addressing",
+        _ => false,
+    };
+    let attribute = |name: &str| el.attributes.get(name).map(|v| v.trim());
+    let optional = matches!(attribute("required"), Some("false") | Some("0"))
+        || matches!(attribute("Optional"), Some("true") | Some("1"));
+    if marker {
+        return !optional;
+    }
+
+    (el.attributes.contains_key("Action") && el.attributes.contains_key("message"))
+        || el
+            .children
+            .iter()
+            .filter_map(|c| c.as_element())
+            .any(uses_addressing)
+}
+
+/// Headers of a binding input or output, which may be in the root part of a
+/// `mime:multipartRelated`.
+fn parse_soap_headers(root_el: &Element, el: &Element) -> Result<Vec<SoapHeader>, WsdlError> {
+    let mime_parts = el
+        .get_child("multipartRelated")
+        .into_iter()
+        .flat_map(|m| m.children.iter().filter_map(|c| c.as_element()))
+        .filter(|c| c.name == "part");
+
+    let mut headers = vec![];
+    for header in std::iter::once(el)
+        .chain(mime_parts)
+        .flat_map(|e| e.children.iter().filter_map(|c| c.as_element()))
+        .filter(|c| c.name == "header")
+    {
+        let message = split_namespace(
+            header
+                .attributes
+                .get("message")
+                .ok_or(WsdlError::AttributeNotFound("message"))?,
+        );
+        let part = header
+            .attributes
+            .get("part")
+            .ok_or(WsdlError::AttributeNotFound("part"))?;
+        let element = root_el
+            .children
+            .iter()
+            .filter_map(|c| c.as_element())
+            .find(|c| {
+                c.name == "message" && c.attributes.get("name").map(|n| n.as_str()) == Some(message)
+            })
+            .ok_or(WsdlError::ElementNotFound("message"))?
+            .children
+            .iter()
+            .filter_map(|c| c.as_element())
+            .find(|c| c.name == "part" && c.attributes.get("name") == Some(part))
+            .ok_or(WsdlError::ElementNotFound("part"))?
+            .attributes
+            .get("element")
+            .ok_or(WsdlError::AttributeNotFound("element"))?;
+
+        let element = split_namespace(element);
+
+        // elements of a built-in type are not in the parsed types
+        let (simple_type, namespace) = match find_global_element(root_el, element) {
+            Some((declaration, namespace)) => match declaration
+                .attributes
+                .get("type")
+                .map(|t| parse_simple_type(t))
+            {
+                Some(SimpleType::Complex(_)) | None => (None, None),
+                Some(t) => (Some(t), namespace),
+            },
+            None => (None, None),
+        };
+
+        headers.push(SoapHeader {
+            message: message.to_string(),
+            part: part.to_string(),
+            element: element.to_string(),
+            simple_type,
+            namespace,
+        });
+    }
+    Ok(headers)
+}
+
+/// Declaration of a global schema element, with the namespace of its schema.
+fn find_global_element<'a>(
+    root_el: &'a Element,
+    name: &str,
+) -> Option<(&'a Element, Option<String>)> {
+    root_el
+        .get_child("types")?
+        .children
+        .iter()
+        .filter_map(|c| c.as_element())
+        .filter(|c| c.name == "schema")
+        .find_map(|schema| {
+            schema
+                .children
+                .iter()
+                .filter_map(|c| c.as_element())
+                .find(|c| {
+                    c.name == "element"
+                        && c.attributes.get("name").map(|n| n.as_str()) == Some(name)
+                })
+                .map(|element| (element, schema.attributes.get("targetNamespace").cloned()))
+        })
+}
+
+/// Parts of a binding input or output sent as attachments, skipping the one holding the
+/// envelope.
+fn parse_mime_parts(el: &Element) -> Vec<MimePart> {
+    let mut parts: Vec<MimePart> = Vec::new();
+    let mime_parts = el
+        .get_child("multipartRelated")
+        .into_iter()
+        .flat_map(|m| m.children.iter().filter_map(|c| c.as_element()))
+        .filter(|c| c.name == "part");
+
+    for content in mime_parts
+        .flat_map(|p| p.children.iter().filter_map(|c| c.as_element()))
+        .filter(|c| c.name == "content")
+    {
+        let part = match content.attributes.get("part") {
+            Some(part) => part,
+            None => continue,
+        };
+        let index = match parts.iter().position(|p| &p.part == part) {
+            Some(index) => index,
+            None => {
+                parts.push(MimePart {
+                    part: part.to_string(),
+                    content_types: vec![],
+                });
+                parts.len() - 1
+            }
+        };
+        if let Some(content_type) = content.attributes.get("type") {
+            parts[index].content_types.push(content_type.to_string());
+        }
+    }
+
+    parts
+}
+
+pub fn parse_ports(root_el: &Element) -> Result<Vec<Port>, WsdlError> {
+    let mut ports = Vec::new();
+    if let Some(service_el) = root_el.get_child("service") {
+        for port in service_el
+            .children
+            .iter()
+            .filter_map(|c| c.as_element())
+            .filter(|e| e.name == "port" || e.name == "endpoint")
+        {
+            let name = port
+                .attributes
+                .get("name")
+                .ok_or(WsdlError::AttributeNotFound("name"))?;
+            let binding = port
+                .attributes
+                .get("binding")
+                .ok_or(WsdlError::AttributeNotFound("binding"))?;
+            let location = match port.attributes.get("address") {
+                Some(address) => Some(address.to_string()),
+                None => port
+                    .get_child("address")
+                    .and_then(|a| a.attributes.get("location"))
+                    .cloned(),
+            };
+
+            ports.push(Port {
+                name: name.to_string(),
+                binding: split_namespace(binding).to_string(),
+                location,
+            });
+        }
+    }
+
+    Ok(ports)
+}
+
+pub fn parse(bytes: &[u8]) -> Result<Wsdl, WsdlError> {
+    let elements = Element::parse(bytes)?;
+    // trace!("elements: {:#?}", elements);
+
+    let target_namespace = match elements.get_child("import") {
+        Some(namespace_el) => namespace_el
+            .attributes
+            .get("namespace")
+            .ok_or(WsdlError::AttributeNotFound("namespace"))?
+            .to_string(),
+        None => elements
+            .attributes
+            .get("targetNamespace")
+            .ok_or(WsdlError::AttributeNotFound("targetNamespace"))?
+            .to_string(),
+    };
+
+    let service_el = elements
+        .get_child("service")
+        .ok_or(WsdlError::ElementNotFound("service"))?;
+    let service_name = service_el
+        .attributes
+        .get("name")
+        .ok_or(WsdlError::AttributeNotFound("name"))?;
+
+    let types = parse_types(&elements)?;
+    let messages = parse_messages(&elements)?;
+    let operations = parse_operations(&elements)?;
+    let ports = parse_ports(&elements)?;
+
+    //FIXME: ignoring bindings for now
+
+    debug!("service name: {}", service_name);
+    debug!("parsed types: {:#?}", types);
+    debug!("parsed messages: {:#?}", messages);
+    debug!("parsed operations: {:#?}", operations);
+
+    Ok(Wsdl {
+        name: service_name.to_string(),
+        target_namespace,
+        types,
+        messages,
+        operations,
+        ports,
+        documentation: parse_documentation(service_el),
+        addressing: uses_addressing(&elements),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_test(bytes: &[u8]) {
+        let wsdl = parse(bytes).unwrap();
+        debug!("wsdl: {:#?}", wsdl);
+    }
+
+    #[test]
+    fn parse_example() {
+        parse_test(include_bytes!("../../assets/example.wsdl"));
+    }
+
+    #[test]
+    fn parse_wikipedia() {
+        parse_test(include_bytes!("../../assets/wikipedia-example.wsdl"));
+    }
+
+    #[test]
+    fn parse_whwebservice() {
+        parse_test(include_bytes!("../../assets/WHWebService.wsdl"));
+    }
+
+    #[test]
+    fn parse_service_ports() {
+        let wsdl = parse(include_bytes!("../../savon-test/CountryInfoService.wsdl")).unwrap();
+        let port = wsdl
+            .ports
+            .iter()
+            .find(|p| p.name == "CountryInfoServiceSoap")
+            .unwrap();
+        assert_eq!(port.binding, "CountryInfoServiceSoapBinding");
+        assert_eq!(
+            port.location.as_deref(),
+            Some("http://webservices.oorsprong.org/websamples.countryinfo/CountryInfoService.wso")
+        );
+    }
+
+    #[test]
+    fn parse_mime_bindings() {
+        let wsdl = parse(include_bytes!("../../savon-test/features.wsdl")).unwrap();
+        let upload = &wsdl.operations["Upload"];
+        assert_eq!(upload.input_attachments.len(), 1);
+        assert_eq!(upload.input_attachments[0].part, "scan");
+        assert_eq!(
+            upload.input_attachments[0].content_types,
+            ["image/png", "image/jpeg"]
+        );
+        assert!(upload.output_attachments.is_empty());
+        assert_eq!(wsdl.messages["UploadSoapIn"].part_element, "Attachment");
+        assert!(wsdl.operations["PlaceOrder"].input_attachments.is_empty());
+    }
+
+    #[test]
+    fn reports_invalid_schemas() {
+        let features = include_str!("../../savon-test/features.wsdl");
+
+        let wsdl = features.replacen(r#"minOccurs="0""#, r#"minOccurs="none""#, 1);
+        assert!(matches!(
+            parse(wsdl.as_bytes()),
+            Err(WsdlError::InvalidOccurence(ref n)) if n == "none"
+        ));
+
+        let wsdl = features.replace(
+            r#"<xs:element name="Locale" type="xs:string"/>"#,
+            r#"<xs:simpleType name="Unit"><xs:restriction base="xs:string"/></xs:simpleType>"#,
+        );
+        assert!(matches!(
+            parse(wsdl.as_bytes()),
+            Err(WsdlError::UnsupportedType { ref name, ref kind }) if name == "Unit" && kind == "restriction"
+        ));
+    }
+
+    #[test]
+    fn parse_every_schema() {
+        let features = include_str!("../../savon-test/features.wsdl");
+        let wsdl = features.replace(
+            "</xs:schema>",
+            r#"</xs:schema>
+    <xs:schema elementFormDefault="qualified" targetNamespace="http://example.com/extra">
+      <xs:complexType name="Extra">
+        <xs:sequence>
+          <xs:element name="value" type="xs:string"/>
+        </xs:sequence>
+      </xs:complexType>
+    </xs:schema>"#,
+        );
+        let wsdl = parse(wsdl.as_bytes()).unwrap();
+
+        match (&wsdl.types["Item"], &wsdl.types["Extra"]) {
+            (Type::Complex(item), Type::Complex(extra)) => {
+                assert_eq!(
+                    item.namespace.as_deref(),
+                    Some("http://example.com/features")
+                );
+                assert_eq!(extra.namespace.as_deref(), Some("http://example.com/extra"));
+                assert_eq!(extra.fields[0].0, "value");
+            }
+            types => panic!("expected complex types, got {:?}", types),
+        }
+    }
+
+    #[test]
+    fn parse_type_referencing_elements() {
+        let names = include_str!("../../savon-test/names.wsdl");
+        let start = names.find(r#"<xs:element name="Ping">"#).unwrap();
+        let end = start + names[start..].find("</xs:element>").unwrap() + "</xs:element>".len();
+        // the named type is defined after the element referencing it
+        let wsdl = names.replace(
+            &names[start..end],
+            r#"<xs:element name="Ping" type="tns:PingType"/>"#,
+        );
+        let wsdl = wsdl.replace(
+            "</xs:schema>",
+            r#"<xs:complexType name="PingType">
+        <xs:sequence>
+          <xs:element name="value" type="xs:string"/>
+        </xs:sequence>
+      </xs:complexType>
+      <xs:element name="Count" type="xs:int"/>
+    </xs:schema>"#,
+        );
+        let parsed = parse(wsdl.as_bytes()).unwrap();
+
+        match &parsed.types["Ping"] {
+            Type::Complex(ping) => {
+                assert_eq!(ping.namespace.as_deref(), Some("http://example.com/names"));
+                assert_eq!(ping.fields[0].0, "value");
+            }
+            t => panic!("expected a complex type, got {:?}", t),
+        }
+        assert!(matches!(
+            parsed.types["Count"],
+            Type::Simple(SimpleType::Int)
+        ));
+
+        let unknown = wsdl.replace("tns:PingType", "tns:Missing");
+        assert!(matches!(
+            parse(unknown.as_bytes()),
+            Err(WsdlError::UnknownType { name, type_name }) if name == "Ping" && type_name == "tns:Missing"
+        ));
+    }
+
+    #[test]
+    fn parse_header_bindings() {
+        let wsdl = parse(include_bytes!("../../savon-test/features.wsdl")).unwrap();
+        let headers = &wsdl.operations["PlaceOrder"].input_headers;
+        assert_eq!(headers.len(), 1);
+        assert_eq!(headers[0].message, "SessionHeader");
+        assert_eq!(headers[0].part, "session");
+        assert_eq!(headers[0].element, "Session");
+        assert!(headers[0].simple_type.is_none());
+
+        // headers in the root part of a multipart binding, of complex and simple types
+        let headers = &wsdl.operations["Upload"].input_headers;
+        assert_eq!(headers.len(), 2);
+        assert_eq!(headers[1].element, "Locale");
+        assert!(matches!(headers[1].simple_type, Some(SimpleType::String)));
+        assert_eq!(
+            headers[1].namespace.as_deref(),
+            Some("http://example.com/features")
+        );
+    }
+
+    #[test]
+    fn parse_addressing_actions() {
+        let wsdl = parse(include_bytes!("../../savon-test/features.wsdl")).unwrap();
+        assert!(wsdl.addressing);
+        let place_order = &wsdl.operations["PlaceOrder"];
+        assert_eq!(
+            place_order.input_action.as_deref(),
+            Some("http://example.com/features/PlaceOrder")
+        );
+        // actions default to `{targetNamespace}/{portType}/{operation}Request` or `Response`
+        assert_eq!(
+            place_order.output_action.as_deref(),
+            Some("http://example.com/features/FeaturesSoap/PlaceOrderResponse")
+        );
+        assert_eq!(
+            wsdl.operations["Upload"].input_action.as_deref(),
+            Some("http://example.com/features/FeaturesSoap/UploadRequest")
+        );
+
+        let wsdl = parse(include_bytes!("../../savon-test/CountryInfoService.wsdl")).unwrap();
+        assert!(!wsdl.addressing);
+    }
+
+    #[test]
+    fn parse_addressing_markers() {
+        let with_marker = |marker: &str| {
+            let wsdl = include_str!("../../savon-test/CountryInfoService.wsdl").replacen(
+                r#"<soap:binding style="document""#,
+                &format!(r#"{}<soap:binding style="document""#, marker),
+                1,
+            );
+            parse(wsdl.as_bytes()).unwrap().addressing
+        };
+        const WSAW: &str = r#"xmlns:wsaw="http://www.w3.org/2006/05/addressing/wsdl""#;
+        const WSAM: &str = r#"xmlns:wsam="http://www.w3.org/2007/05/addressing/metadata""#;
+        const WSP: &str = r#"xmlns:wsp="http://www.w3.org/ns/ws-policy""#;
+
+        assert!(with_marker(&format!("<wsaw:UsingAddressing {}/>", WSAW)));
+        assert!(with_marker(&format!(
+            "<wsp:Policy {}><wsam:Addressing {}/></wsp:Policy>",
+            WSP, WSAM
+        )));
+        // optional addressing
+        assert!(!with_marker(&format!(
+            r#"<wsaw:UsingAddressing {} xmlns:wsdl="http://schemas.xmlsoap.org/wsdl/" wsdl:required="false"/>"#,
+            WSAW
+        )));
+        assert!(!with_marker(&format!(
+            r#"<wsp:Policy {wsp}><wsam:Addressing {} wsp:Optional="true"/></wsp:Policy>"#,
+            WSAM,
+            wsp = WSP
+        )));
+        // same names in other namespaces
+        assert!(!with_marker(
+            r#"<UsingAddressing xmlns="urn:other"/><x:Addressing xmlns:x="urn:other"/>"#
+        ));
+    }
+}
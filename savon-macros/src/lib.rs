@@ -0,0 +1,77 @@
+//! Procedural macros for savon, re-exported by `savon` with its `macros` feature.
+//!
+//! ```ignore
+//! mod soap {
+//!     savon::wsdl!("assets/example.wsdl");
+//! }
+//! ```
+extern crate proc_macro;
+
+use proc_macro2::TokenStream;
+use quote::quote;
+use std::path::PathBuf;
+use syn::{parse_macro_input, LitStr};
+
+/// Generates the client for a WSDL file in place, as `savon::gen::gen_write` does from a
+/// `build.rs`. The path is relative to the crate's `Cargo.toml`.
+#[proc_macro]
+pub fn wsdl(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let path = parse_macro_input!(input as LitStr);
+    expand(&path)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+fn expand(path: &LitStr) -> syn::Result<TokenStream> {
+    let full_path = match std::env::var_os("CARGO_MANIFEST_DIR") {
+        Some(dir) => PathBuf::from(dir).join(path.value()),
+        None => PathBuf::from(path.value()),
+    };
+    let error = |message: String| syn::Error::new(path.span(), message);
+
+    let bytes = std::fs::read(&full_path)
+        .map_err(|e| error(format!("cannot read {}: {}", full_path.display(), e)))?;
+    let wsdl = savon_gen::wsdl::parse(&bytes[..])
+        .map_err(|e| error(format!("invalid WSDL {}: {:?}", full_path.display(), e)))?;
+    let tokens = savon_gen::gen::gen_tokens(&wsdl).map_err(|e| {
+        error(format!(
+            "cannot generate code for {}: {:?}",
+            full_path.display(),
+            e
+        ))
+    })?;
+
+    // rebuild when the WSDL file changes
+    let full_path = full_path.to_string_lossy();
+    Ok(quote! {
+        const _: &[u8] = include_bytes!(#full_path);
+
+        #tokens
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_errors_on_the_path() {
+        let path = LitStr::new("missing.wsdl", proc_macro2::Span::call_site());
+        let error = expand(&path).unwrap_err();
+        assert!(error.to_string().starts_with("cannot read"));
+
+        let path = LitStr::new("../assets/example.wsdl", proc_macro2::Span::call_site());
+        assert!(expand(&path).is_ok());
+
+        // schema errors become a compile_error! rather than a panic
+        let wsdl = std::fs::read_to_string("../assets/example.wsdl")
+            .unwrap()
+            .replacen("<element name=", r#"<element minOccurs="some" name="#, 2);
+        let invalid = std::env::temp_dir().join("savon-macros-invalid.wsdl");
+        std::fs::write(&invalid, wsdl).unwrap();
+        let path = LitStr::new(&invalid.to_string_lossy(), proc_macro2::Span::call_site());
+        let error = expand(&path).unwrap_err().into_compile_error().to_string();
+        assert!(error.starts_with(":: core :: compile_error !"));
+        assert!(error.contains("InvalidOccurence"));
+    }
+}
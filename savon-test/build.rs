@@ -5,4 +5,21 @@ fn main() {
     savon::gen::gen_write("./CountryInfoService.wsdl", &out_dir).unwrap();
     savon::gen::gen_write("./example.wsdl", &out_dir).unwrap();
     savon::gen::gen_write("../assets/WHWebService.wsdl", &out_dir).unwrap();
+    savon::gen::gen_write("./features.wsdl", &out_dir).unwrap();
+    savon::gen::gen_write("./names.wsdl", &out_dir).unwrap();
+    savon::gen::Builder::new()
+        .module("configured")
+        .file_name("features_configured.rs")
+        .derive("PartialEq")
+        .derive("serde::Serialize")
+        .derive("serde::Deserialize")
+        .type_name("Item", "LineItem")
+        .field_name("Item", "quantity", "count")
+        .visibility("pub(crate)")
+        .generate_client(false)
+        .blocking_client(true)
+        .emit_docs(false)
+        .float_as_decimal(true)
+        .write("./features.wsdl", &out_dir)
+        .unwrap();
 }
@@ -11,6 +11,30 @@ mod soap1 {
     include!(concat!(env!("OUT_DIR"), "/example.rs"));
 }
 
+mod soap2 {
+    include!(concat!(env!("OUT_DIR"), "/w_h_web_service.rs"));
+}
+
+#[allow(dead_code)]
+mod features {
+    include!(concat!(env!("OUT_DIR"), "/features.rs"));
+}
+
+#[allow(dead_code)]
+mod names {
+    include!(concat!(env!("OUT_DIR"), "/names.rs"));
+}
+
+#[allow(dead_code)]
+mod features_inline {
+    savon::wsdl!("features.wsdl");
+}
+
+#[allow(dead_code)]
+mod features_configured {
+    include!(concat!(env!("OUT_DIR"), "/features_configured.rs"));
+}
+
 #[tokio::main]
 async fn main() -> Result<(), savon::Error> {
     pretty_env_logger::init();
@@ -30,3 +54,749 @@ async fn main() -> Result<(), savon::Error> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::features::types;
+    use savon::gen::{FromElement, ToElements};
+    use savon::internal::xmltree::Element;
+    use savon::rpser::{xml::BuildElement, Method, Response};
+
+    const NS: &str = "http://example.com/features";
+
+    fn item(name: &str, quantity: i64) -> types::Item {
+        types::Item {
+            name: name.to_string(),
+            quantity,
+        }
+    }
+
+    fn wrap(order: &types::PlaceOrder) -> Element {
+        Element::node("PlaceOrder")
+            .with_namespace(NS)
+            .with_children(order.to_elements())
+    }
+
+    #[test]
+    fn repeated_elements_round_trip() {
+        let order = types::PlaceOrder {
+            id: "42".to_string(),
+            tags: vec!["urgent".to_string(), "gift".to_string()],
+            items: vec![item("pen", 2), item("ink", 1)],
+            coupon: Some("SPRING".to_string()),
+            ..Default::default()
+        };
+
+        let element = wrap(&order);
+        let names = element
+            .children
+            .iter()
+            .filter_map(|c| c.as_element())
+            .map(|e| e.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["id", "tags", "tags", "items", "items", "coupon"]);
+
+        let parsed = types::PlaceOrder::from_element(&element).unwrap();
+        assert_eq!(parsed.tags, order.tags);
+        assert_eq!(parsed.items.len(), 2);
+        assert_eq!(parsed.items[1].name, "ink");
+        assert_eq!(parsed.items[1].quantity, 1);
+    }
+
+    #[test]
+    fn repeated_elements_only_match_their_name() {
+        let xml = format!(
+            r#"<PlaceOrder xmlns="{ns}">
+                <id>1</id>
+                <coupon>c</coupon>
+                <items><name>pen</name><quantity>1</quantity></items>
+                <other xmlns="urn:other"><name>x</name><quantity>0</quantity></other>
+                <items xmlns="urn:other"><name>y</name><quantity>0</quantity></items>
+            </PlaceOrder>"#,
+            ns = NS
+        );
+        let element = Element::parse(xml.as_bytes()).unwrap();
+
+        let parsed = types::PlaceOrder::from_element(&element).unwrap();
+        assert!(parsed.tags.is_empty());
+        assert_eq!(parsed.items.len(), 1);
+        assert_eq!(parsed.items[0].name, "pen");
+    }
+
+    #[test]
+    fn repeated_elements_enforce_occurences() {
+        let mut order = types::PlaceOrder {
+            id: "42".to_string(),
+            coupon: Some("c".to_string()),
+            ..Default::default()
+        };
+        assert!(types::PlaceOrder::from_element(&wrap(&order)).is_err());
+
+        order.items = vec![item("a", 1), item("b", 1), item("c", 1), item("d", 1)];
+        assert!(types::PlaceOrder::from_element(&wrap(&order)).is_err());
+
+        order.items.pop();
+        assert!(types::PlaceOrder::from_element(&wrap(&order)).is_ok());
+    }
+
+    #[test]
+    fn nil_elements_differ_from_absent_ones() {
+        let order = types::PlaceOrder {
+            id: "42".to_string(),
+            items: vec![item("pen", 1)],
+            coupon: None,
+            gift_message: savon::gen::Nillable::Nil,
+            ratings: vec![Some(5), None],
+            ..Default::default()
+        };
+
+        let element = wrap(&order);
+        assert!(element.find_child("note", Some(NS)).is_none());
+        assert!(element.find_child("coupon", Some(NS)).unwrap().is_nil());
+        assert!(element
+            .find_child("giftMessage", Some(NS))
+            .unwrap()
+            .is_nil());
+
+        let xml = order
+            .to_elements()
+            .into_iter()
+            .fold(Method::new("PlaceOrder"), Method::with)
+            .as_xml(NS);
+        let parsed =
+            types::PlaceOrder::from_element(&Response::from_xml(&xml).unwrap().body).unwrap();
+        assert_eq!(parsed.note, None);
+        assert_eq!(parsed.coupon, None);
+        assert_eq!(parsed.gift_message, savon::gen::Nillable::Nil);
+        assert_eq!(parsed.ratings, vec![Some(5), None]);
+
+        let order = types::PlaceOrder {
+            gift_message: savon::gen::Nillable::Absent,
+            ..order
+        };
+        let parsed = types::PlaceOrder::from_element(&wrap(&order)).unwrap();
+        assert_eq!(parsed.gift_message, savon::gen::Nillable::Absent);
+    }
+
+    #[test]
+    fn optional_elements_keep_parse_errors() {
+        let xml = format!(
+            r#"<PlaceOrder xmlns="{ns}">
+                <id>1</id>
+                <items><name>pen</name><quantity>1</quantity></items>
+                <priority>high</priority>
+                <coupon>c</coupon>
+            </PlaceOrder>"#,
+            ns = NS
+        );
+        let element = Element::parse(xml.as_bytes()).unwrap();
+
+        assert!(types::PlaceOrder::from_element(&element).is_err());
+    }
+
+    #[test]
+    fn required_nillable_elements_must_be_present() {
+        let xml = format!(
+            r#"<PlaceOrder xmlns="{ns}">
+                <id>1</id>
+                <items><name>pen</name><quantity>1</quantity></items>
+            </PlaceOrder>"#,
+            ns = NS
+        );
+        let element = Element::parse(xml.as_bytes()).unwrap();
+
+        assert!(types::PlaceOrder::from_element(&element).is_err());
+    }
+
+    fn ship(shipment: &types::Shipment) -> Response {
+        let method = shipment
+            .to_attributes()
+            .into_iter()
+            .fold(Method::new("Shipment"), |m, (ns, name, value)| {
+                m.with_attr(ns, &name, &value)
+            });
+        let xml = shipment
+            .to_elements()
+            .into_iter()
+            .fold(method, Method::with)
+            .as_xml(NS);
+        Response::from_xml(&xml).unwrap()
+    }
+
+    #[test]
+    fn defaults_come_from_the_schema() {
+        let shipment = types::Shipment::default();
+        assert_eq!(shipment.method, "standard");
+        assert_eq!(shipment.version, "1.0");
+        assert_eq!(shipment.attempts, 3);
+        assert!(!shipment.express);
+        assert_eq!(shipment.weight, None);
+        assert_eq!(shipment.unit, "kg");
+
+        let xml = format!(r#"<Shipment xmlns="{ns}" carrier="post"/>"#, ns = NS);
+        let element = Element::parse(xml.as_bytes()).unwrap();
+        let parsed = types::Shipment::from_element(&element).unwrap();
+        assert_eq!(parsed.carrier, "post");
+        assert_eq!(parsed.method, "standard");
+        assert_eq!(parsed.attempts, 3);
+        assert!(!parsed.express);
+        assert_eq!(parsed.unit, "kg");
+    }
+
+    #[test]
+    fn attributes_round_trip() {
+        let shipment = types::Shipment {
+            carrier: "post".to_string(),
+            express: true,
+            weight: Some(12),
+            method: "overnight".to_string(),
+            ..Default::default()
+        };
+
+        let response = ship(&shipment);
+        assert_eq!(response.body.attributes["unit"], "kg");
+        let parsed = types::Shipment::from_element(&response.body).unwrap();
+        assert_eq!(parsed.carrier, "post");
+        assert!(parsed.express);
+        assert_eq!(parsed.weight, Some(12));
+        assert_eq!(parsed.method, "overnight");
+
+        let xml = format!(r#"<Shipment xmlns="{ns}"/>"#, ns = NS);
+        let element = Element::parse(xml.as_bytes()).unwrap();
+        assert!(types::Shipment::from_element(&element).is_err());
+    }
+
+    #[test]
+    fn fixed_values_are_always_sent_and_checked() {
+        let shipment = types::Shipment {
+            carrier: "post".to_string(),
+            version: "2.0".to_string(),
+            ..Default::default()
+        };
+
+        let response = ship(&shipment);
+        let version = response.body.find_child("version", Some(NS)).unwrap();
+        assert_eq!(version.get_text().unwrap(), "1.0");
+
+        let xml = format!(
+            r#"<Shipment xmlns="{ns}" carrier="post"><version>2.0</version></Shipment>"#,
+            ns = NS
+        );
+        let element = Element::parse(xml.as_bytes()).unwrap();
+        assert!(types::Shipment::from_element(&element).is_err());
+
+        let xml = format!(
+            r#"<Shipment xmlns="{ns}" carrier="post" unit="lb"/>"#,
+            ns = NS
+        );
+        let element = Element::parse(xml.as_bytes()).unwrap();
+        assert!(types::Shipment::from_element(&element).is_err());
+    }
+
+    #[test]
+    fn builder_options_apply_to_generated_code() {
+        use super::features_configured::configured::types as configured;
+
+        let order = configured::PlaceOrder {
+            id: "42".to_string(),
+            items: vec![configured::LineItem {
+                name: "pen".to_string(),
+                count: 2,
+            }],
+            coupon: Some("c".to_string()),
+            ..Default::default()
+        };
+
+        let element = Element::node("PlaceOrder")
+            .with_namespace(NS)
+            .with_children(order.to_elements());
+        let item = element.find_child("items", Some(NS)).unwrap();
+        assert!(item.find_child("quantity", Some(NS)).is_some());
+
+        let parsed = configured::PlaceOrder::from_element(&element).unwrap();
+        assert_eq!(parsed, order);
+
+        fn serializable<T: serde::Serialize + serde::de::DeserializeOwned>(_: &T) {}
+        serializable(&order);
+    }
+
+    #[test]
+    fn macro_generates_the_same_client() {
+        use super::features_inline::types as inline;
+
+        let order = inline::PlaceOrder {
+            id: "42".to_string(),
+            items: vec![inline::Item {
+                name: "pen".to_string(),
+                quantity: 2,
+            }],
+            coupon: Some("c".to_string()),
+            ..Default::default()
+        };
+        let element = Element::node("PlaceOrder")
+            .with_namespace(NS)
+            .with_children(order.to_elements());
+
+        let parsed = types::PlaceOrder::from_element(&element).unwrap();
+        assert_eq!(parsed.id, "42");
+        assert_eq!(parsed.items[0].quantity, 2);
+        let _client = super::features_inline::FeaturesService::new(String::new());
+    }
+
+    #[test]
+    fn sanitized_names_keep_xml_names() {
+        let naming = types::Naming {
+            r#type: "t".to_string(),
+            self_: "s".to_string(),
+            _1st: "1".to_string(),
+            order_id: "dash".to_string(),
+            order_id_2: "underscore".to_string(),
+            result: types::Result2 { code: 7 },
+            r#ref: Some("r".to_string()),
+        };
+
+        let xml = naming
+            .to_elements()
+            .into_iter()
+            .fold(
+                Method::new("Naming").with_attr(None, "ref", "r"),
+                Method::with,
+            )
+            .as_xml(NS);
+        let body = Response::from_xml(&xml).unwrap().body;
+        let names = body
+            .children
+            .iter()
+            .filter_map(|c| c.as_element())
+            .map(|e| e.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            ["type", "self", "1st", "order-id", "order_id", "result"]
+        );
+
+        let parsed = types::Naming::from_element(&body).unwrap();
+        assert_eq!(parsed.order_id, "dash");
+        assert_eq!(parsed.order_id_2, "underscore");
+        assert_eq!(parsed.result.code, 7);
+        assert_eq!(parsed.r#ref.as_deref(), Some("r"));
+    }
+
+    #[test]
+    fn recursive_types_round_trip() {
+        let leaf = |value: &str| types::TreeNode {
+            value: value.to_string(),
+            ..Default::default()
+        };
+        let tree = types::TreeNode {
+            value: "root".to_string(),
+            left: Some(Box::new(leaf("left"))),
+            right: savon::gen::Nillable::Nil,
+            children: vec![leaf("a"), leaf("b")],
+            owner: Some(Box::new(types::Owner {
+                name: "me".to_string(),
+                root: Box::new(leaf("owned")),
+            })),
+        };
+
+        let element = Element::node("TreeNode")
+            .with_namespace(NS)
+            .with_children(tree.to_elements());
+        let parsed = types::TreeNode::from_element(&element).unwrap();
+        assert_eq!(parsed.left.unwrap().value, "left");
+        assert!(parsed.right.is_nil());
+        assert_eq!(parsed.children.len(), 2);
+        assert_eq!(parsed.owner.unwrap().root.value, "owned");
+    }
+
+    #[test]
+    fn dates_and_durations_round_trip() {
+        use savon::internal::chrono::{DateTime, NaiveDate};
+
+        let xml = format!(
+            r#"<Schedule xmlns="{ns}" created="2021-01-01T00:00:00">
+                <start>2021-06-01T09:30:00.250+02:00</start>
+                <day>2021-06-01Z</day>
+                <length>P1DT2H</length>
+            </Schedule>"#,
+            ns = NS
+        );
+        let element = Element::parse(xml.as_bytes()).unwrap();
+        let schedule = types::Schedule::from_element(&element).unwrap();
+        assert_eq!(
+            schedule.start,
+            DateTime::parse_from_rfc3339("2021-06-01T09:30:00.25+02:00").unwrap()
+        );
+        assert_eq!(schedule.day, NaiveDate::from_ymd_opt(2021, 6, 1).unwrap());
+        assert_eq!(schedule.at, None);
+        assert_eq!(schedule.length.days, 1);
+        assert_eq!(
+            schedule.created.unwrap().to_rfc3339(),
+            "2021-01-01T00:00:00+00:00"
+        );
+
+        let texts = schedule
+            .to_elements()
+            .into_iter()
+            .map(|e| e.get_text().unwrap().into_owned())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            texts,
+            ["2021-06-01T09:30:00.250+02:00", "2021-06-01", "P1DT2H"]
+        );
+        assert_eq!(schedule.to_attributes()[0].2, "2021-01-01T00:00:00Z");
+    }
+
+    #[test]
+    fn decimals_are_exact() {
+        use super::features_configured::configured::types as configured;
+
+        let xml = format!(
+            r#"<Invoice xmlns="{ns}"><amount>0.10</amount><rate>1.1</rate></Invoice>"#,
+            ns = NS
+        );
+        let element = Element::parse(xml.as_bytes()).unwrap();
+
+        let invoice = types::Invoice::from_element(&element).unwrap();
+        assert_eq!(invoice.amount.to_string(), "0.10");
+        assert_eq!(invoice.rate, 1.1);
+        let texts = invoice
+            .to_elements()
+            .into_iter()
+            .map(|e| e.get_text().unwrap().into_owned())
+            .collect::<Vec<_>>();
+        assert_eq!(texts, ["0.10", "1.1"]);
+
+        let invoice = configured::Invoice::from_element(&element).unwrap();
+        assert_eq!(invoice.rate.to_string(), "1.1");
+    }
+
+    #[test]
+    fn binary_fields_round_trip() {
+        let xml = format!(
+            r#"<Attachment xmlns="{ns}" checksum="CAFE"><content>
+                c2F2
+                b24=
+            </content></Attachment>"#,
+            ns = NS
+        );
+        let element = Element::parse(xml.as_bytes()).unwrap();
+
+        let attachment = types::Attachment::from_element(&element).unwrap();
+        assert_eq!(attachment.content, b"savon");
+        assert_eq!(attachment.thumbnail, None);
+        assert_eq!(attachment.checksum, [0xca, 0xfe]);
+
+        let attachment = types::Attachment {
+            thumbnail: Some(vec![0]),
+            ..attachment
+        };
+        let texts = attachment
+            .to_elements()
+            .into_iter()
+            .map(|e| e.get_text().unwrap().into_owned())
+            .collect::<Vec<_>>();
+        assert_eq!(texts, ["c2F2b24=", "AA=="]);
+        assert_eq!(
+            attachment.to_attributes(),
+            [(None, "checksum".to_string(), "CAFE".to_string())]
+        );
+
+        let invalid = Element::parse(
+            format!(
+                r#"<Attachment xmlns="{}" checksum="CAF"><content/></Attachment>"#,
+                NS
+            )
+            .as_bytes(),
+        )
+        .unwrap();
+        assert!(types::Attachment::from_element(&invalid).is_err());
+    }
+
+    #[test]
+    fn attachment_operations_are_generated() {
+        use super::features::{messages, FeaturesService};
+        use savon::http::Attachment;
+
+        // only operations with a `mime:multipartRelated` binding get the method
+        let _upload = FeaturesService::upload_with_attachments;
+        let _upload = FeaturesService::upload;
+
+        let scan = Attachment::new("scan", "image/png", vec![1, 2, 3]);
+        let request = messages::UploadSoapIn(types::Attachment {
+            content: vec![],
+            thumbnail: None,
+            checksum: vec![0xca, 0xfe],
+        });
+        assert_eq!(request.to_elements().len(), 1);
+        assert!(scan.href().starts_with("cid:scan="));
+    }
+
+    #[test]
+    fn declared_headers_are_sent_in_the_header() {
+        let session = types::Session {
+            token: "s3cr3t".to_string(),
+        };
+        let header = savon::gen::to_element("Session", Some(NS), &session);
+        let xml = Method::new("PlaceOrder").with_header(header).as_xml(NS);
+        assert!(xml.contains(
+            "<soap:Header><ns:Session><ns:token>s3cr3t</ns:token></ns:Session></soap:Header>"
+        ));
+
+        let response = format!(
+            r#"<soap:Envelope xmlns:soap="http://schemas.xmlsoap.org/soap/envelope/">
+                <soap:Header><Session xmlns="{ns}"><token>renewed</token></Session></soap:Header>
+                <soap:Body><PlaceOrderResponse xmlns="{ns}"><accepted>true</accepted></PlaceOrderResponse></soap:Body>
+            </soap:Envelope>"#,
+            ns = NS
+        );
+        let response = Response::from_xml(&response).unwrap();
+        let session = types::Session::from_element(&response.headers[0]).unwrap();
+        assert_eq!(session.token, "renewed");
+    }
+
+    #[tokio::test]
+    async fn simple_headers_are_sent_as_text() {
+        use super::features::{messages, FeaturesService};
+        use savon::transport::{HttpResponse, MockTransport};
+        use std::sync::Arc;
+
+        let transport = Arc::new(MockTransport::new());
+        transport.push_response(HttpResponse::xml(&format!(
+            r#"<soap:Envelope xmlns:soap="http://schemas.xmlsoap.org/soap/envelope/">
+                <soap:Body><PlaceOrderResponse xmlns="{}"><accepted>true</accepted></PlaceOrderResponse></soap:Body>
+            </soap:Envelope>"#,
+            NS
+        )));
+        let client = FeaturesService::with_transport(
+            "http://localhost/features".to_string(),
+            transport.clone(),
+        );
+
+        let upload = messages::UploadSoapIn(types::Attachment {
+            content: b"scan".to_vec(),
+            thumbnail: None,
+            checksum: vec![0xca, 0xfe],
+        });
+        let session = types::Session {
+            token: "s3cr3t".to_string(),
+        };
+        client
+            .upload(upload, session, "fr-FR".to_string())
+            .await
+            .unwrap()
+            .unwrap();
+
+        let body = String::from_utf8_lossy(&transport.requests()[0].body).into_owned();
+        let envelope = Response::from_xml(&body).unwrap();
+        let locale = envelope
+            .headers
+            .iter()
+            .find(|h| h.name == "Locale")
+            .unwrap();
+        assert_eq!(locale.namespace.as_deref(), Some(NS));
+        assert_eq!(locale.get_text().as_deref(), Some("fr-FR"));
+    }
+
+    #[tokio::test]
+    async fn clients_send_through_a_transport() {
+        use super::features::FeaturesService;
+        use savon::transport::{HttpResponse, MockTransport};
+        use std::sync::Arc;
+
+        let transport = Arc::new(MockTransport::new());
+        transport.push_response(HttpResponse::xml(&format!(
+            r#"<soap:Envelope xmlns:soap="http://schemas.xmlsoap.org/soap/envelope/">
+                <soap:Body><PlaceOrderResponse xmlns="{}"><accepted>true</accepted></PlaceOrderResponse></soap:Body>
+            </soap:Envelope>"#,
+            NS
+        )));
+        let client = FeaturesService::with_transport(
+            "http://localhost/features".to_string(),
+            transport.clone(),
+        );
+
+        let accepted = client
+            .place_order_unwrapped(
+                "42".to_string(),
+                vec![],
+                vec![item("soap", 1)],
+                None,
+                None,
+                None,
+                Default::default(),
+                vec![],
+                types::Session {
+                    token: "s3cr3t".to_string(),
+                },
+            )
+            .await
+            .unwrap();
+        assert_eq!(accepted, Ok(true));
+
+        let requests = transport.requests();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].url, "http://localhost/features");
+        let body = String::from_utf8_lossy(&requests[0].body);
+        assert!(body.contains("http://example.com/features/PlaceOrder"));
+        assert!(body.contains("s3cr3t"));
+    }
+
+    #[test]
+    fn blocking_clients_share_types_and_messages() {
+        use super::features_configured::configured::{blocking::FeaturesService, types};
+        use savon::transport::{HttpResponse, MockTransport};
+        use std::sync::Arc;
+
+        let transport = Arc::new(MockTransport::new());
+        transport.push_response(HttpResponse::xml(&format!(
+            r#"<soap:Envelope xmlns:soap="http://schemas.xmlsoap.org/soap/envelope/">
+                <soap:Body><PlaceOrderResponse xmlns="{}"><accepted>false</accepted></PlaceOrderResponse></soap:Body>
+            </soap:Envelope>"#,
+            NS
+        )));
+        let client = FeaturesService::with_transport(
+            "http://localhost/features".to_string(),
+            transport.clone(),
+        );
+
+        // no runtime needed
+        let accepted = client
+            .place_order_unwrapped(
+                "42".to_string(),
+                vec![],
+                vec![types::LineItem {
+                    name: "soap".to_string(),
+                    count: 1,
+                }],
+                None,
+                None,
+                None,
+                Default::default(),
+                vec![],
+                types::Session {
+                    token: "s3cr3t".to_string(),
+                },
+            )
+            .unwrap();
+        assert_eq!(accepted, Ok(false));
+        assert_eq!(transport.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn wrapped_operations_take_and_return_children() {
+        use super::soap::{types, CountryInfoService};
+        use savon::transport::{HttpResponse, MockTransport};
+        use std::sync::Arc;
+
+        const COUNTRY_NS: &str = "http://www.oorsprong.org/websamples.countryinfo";
+        let transport = Arc::new(MockTransport::new());
+        transport.push_response(HttpResponse::xml(&format!(
+            r#"<soap:Envelope xmlns:soap="http://schemas.xmlsoap.org/soap/envelope/">
+                <soap:Body><CurrencyNameResponse xmlns="{}"><CurrencyNameResult>Euro</CurrencyNameResult></CurrencyNameResponse></soap:Body>
+            </soap:Envelope>"#,
+            COUNTRY_NS
+        )));
+        transport.push_response(HttpResponse::xml(&format!(
+            r#"<soap:Envelope xmlns:soap="http://schemas.xmlsoap.org/soap/envelope/">
+                <soap:Body><ListOfContinentsByNameResponse xmlns="{}"><ListOfContinentsByNameResult>
+                    <tContinent><sCode>EU</sCode><sName>Europe</sName></tContinent>
+                </ListOfContinentsByNameResult></ListOfContinentsByNameResponse></soap:Body>
+            </soap:Envelope>"#,
+            COUNTRY_NS
+        )));
+        let client = CountryInfoService::with_transport(
+            "http://localhost/countries".to_string(),
+            transport.clone(),
+        );
+
+        let name = client
+            .currency_name_unwrapped("EUR".to_string())
+            .await
+            .unwrap();
+        assert_eq!(name, Ok("Euro".to_string()));
+        let body = String::from_utf8_lossy(&transport.requests()[0].body).into_owned();
+        let envelope = Response::from_xml(&body).unwrap();
+        assert_eq!(envelope.body.name, "CurrencyName");
+        assert_eq!(
+            envelope
+                .body
+                .get_at_path(&["sCurrencyISOCode"])
+                .unwrap()
+                .get_text()
+                .as_deref(),
+            Some("EUR")
+        );
+
+        let continents = client
+            .list_of_continents_by_name_unwrapped()
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(matches!(
+            continents.t_continent.as_slice(),
+            [Some(types::TContinent { s_code, s_name })] if s_code == "EU" && s_name == "Europe"
+        ));
+    }
+
+    #[tokio::test]
+    async fn declared_faults_are_decoded() {
+        use super::names::{messages, NamesService};
+        use savon::transport::{HttpResponse, MockTransport};
+        use std::sync::Arc;
+
+        const NAMES_NS: &str = "http://example.com/names";
+        let transport = Arc::new(MockTransport::new());
+        transport.push_response(HttpResponse {
+            status: 500,
+            headers: vec![("Content-Type".to_string(), "text/xml".to_string())],
+            body: format!(
+                r#"<soap:Envelope xmlns:soap="http://schemas.xmlsoap.org/soap/envelope/">
+                    <soap:Body><soap:Fault><faultcode>soap:Server</faultcode><faultstring>failed</faultstring>
+                        <detail><Failure xmlns="{}"><reason>down</reason></Failure></detail>
+                    </soap:Fault></soap:Body>
+                </soap:Envelope>"#,
+                NAMES_NS
+            )
+            .into_bytes(),
+        });
+        transport.push_response(HttpResponse::xml(&format!(
+            r#"<soap:Envelope xmlns:soap="http://schemas.xmlsoap.org/soap/envelope/">
+                <soap:Body><PingResponse xmlns="{}"><value>pong</value></PingResponse></soap:Body>
+            </soap:Envelope>"#,
+            NAMES_NS
+        )));
+        let client =
+            NamesService::with_transport("http://localhost/names".to_string(), transport.clone());
+
+        // the unwrapped method of Ping is suffixed, PingUnwrapped taking its name
+        let fault = client
+            .ping_unwrapped_2("ping".to_string())
+            .await
+            .unwrap()
+            .unwrap_err();
+        assert!(matches!(
+            fault,
+            messages::PingError2::PingError(messages::PingError(failure)) if failure.reason == "down"
+        ));
+
+        let pong = client.ping_unwrapped_2("ping".to_string()).await.unwrap();
+        assert!(matches!(pong, Ok(value) if value == "pong"));
+    }
+
+    #[test]
+    fn colliding_names_are_made_unique() {
+        use super::names::{messages, types, NamesService};
+
+        // the fault enum of Ping is named after a message which already exists
+        let fault = messages::PingError2::PingError(messages::PingError(types::Failure {
+            reason: "down".to_string(),
+        }));
+        assert!(matches!(fault, messages::PingError2::PingError(_)));
+
+        // PingUnwrapped is taken by the operation, its unwrapped method gets a suffix
+        let _ = NamesService::ping_unwrapped;
+        let _ = NamesService::ping_unwrapped_2;
+        let _ = NamesService::ping_unwrapped_unwrapped;
+    }
+}
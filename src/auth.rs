@@ -0,0 +1,152 @@
+//! HTTP authentication of a client's requests.
+
+use std::fmt::{self, Debug};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+
+/// Boxed future returned by `TokenProvider`, so it can be used as a trait object.
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// Source of bearer tokens, like an OAuth 2 token endpoint.
+pub trait TokenProvider: Send + Sync {
+    /// Current token, fetched again when missing or expired.
+    fn token(&self) -> BoxFuture<'_, Result<String, crate::Error>>;
+
+    /// Forget the current token after the server rejected it, so the next call to `token`
+    /// gets a fresh one.
+    fn invalidate(&self) {}
+}
+
+/// Authentication sent with every request of a client, through
+/// `savon::http::Options::auth`.
+#[derive(Clone)]
+pub enum Auth {
+    /// HTTP Basic authentication.
+    Basic {
+        username: String,
+        password: Option<String>,
+    },
+    /// Fixed bearer token.
+    Bearer(String),
+    /// Custom header, like an API key.
+    Header { name: String, value: String },
+    /// Bearer token from a provider, refreshed once when the server answers 401 Unauthorized.
+    ///
+    /// Blocking clients run the provider's futures without a runtime, with
+    /// `savon::blocking::block_on`, so they must not need one: a provider using the async
+    /// `reqwest::Client` panics or blocks forever there. Fetch tokens with a blocking
+    /// client instead, or run the async client in a runtime.
+    Token(Arc<dyn TokenProvider>),
+}
+
+impl Debug for Auth {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // credentials are kept out of logs
+        match self {
+            Auth::Basic { username, .. } => f
+                .debug_struct("Basic")
+                .field("username", username)
+                .finish_non_exhaustive(),
+            Auth::Bearer(_) => f.write_str("Bearer(..)"),
+            Auth::Header { name, .. } => f
+                .debug_struct("Header")
+                .field("name", name)
+                .finish_non_exhaustive(),
+            Auth::Token(_) => f.write_str("Token(..)"),
+        }
+    }
+}
+
+impl Auth {
+    /// Basic authentication with a username and password.
+    pub fn basic(username: &str, password: &str) -> Self {
+        Auth::Basic {
+            username: username.to_string(),
+            password: Some(password.to_string()),
+        }
+    }
+
+    /// Bearer authentication with a fixed token.
+    pub fn bearer(token: &str) -> Self {
+        Auth::Bearer(token.to_string())
+    }
+
+    /// Custom header.
+    pub fn header(name: &str, value: &str) -> Self {
+        Auth::Header {
+            name: name.to_string(),
+            value: value.to_string(),
+        }
+    }
+
+    /// Bearer tokens from a provider.
+    pub fn token(provider: impl TokenProvider + 'static) -> Self {
+        Auth::Token(Arc::new(provider))
+    }
+
+    /// Header to send, as `(name, value)`.
+    pub async fn to_header(&self) -> Result<(String, String), crate::Error> {
+        let authorization = "Authorization".to_string();
+        Ok(match self {
+            Auth::Basic { username, password } => {
+                let credentials = format!("{}:{}", username, password.as_deref().unwrap_or(""));
+                (
+                    authorization,
+                    format!("Basic {}", STANDARD.encode(credentials)),
+                )
+            }
+            Auth::Bearer(token) => (authorization, format!("Bearer {}", token)),
+            Auth::Header { name, value } => (name.clone(), value.clone()),
+            Auth::Token(provider) => (authorization, format!("Bearer {}", provider.token().await?)),
+        })
+    }
+
+    /// Whether a rejected request may be sent again, with a refreshed token.
+    pub(crate) fn is_refreshable(&self) -> bool {
+        matches!(self, Auth::Token(_))
+    }
+
+    /// Forget a rejected token, returning whether the request is worth retrying.
+    pub(crate) fn invalidate(&self) -> bool {
+        match self {
+            Auth::Token(provider) => {
+                provider.invalidate();
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn builds_headers() {
+        let header = |auth: Auth| async move { auth.to_header().await.unwrap() };
+        assert_eq!(
+            header(Auth::basic("alice", "secret")).await,
+            (
+                "Authorization".to_string(),
+                "Basic YWxpY2U6c2VjcmV0".to_string()
+            )
+        );
+        assert_eq!(
+            header(Auth::bearer("t0k3n")).await.1,
+            "Bearer t0k3n".to_string()
+        );
+        assert_eq!(
+            header(Auth::header("X-Api-Key", "k3y")).await,
+            ("X-Api-Key".to_string(), "k3y".to_string())
+        );
+
+        let debug = format!("{:?}", Auth::basic("alice", "secret"));
+        assert!(debug.contains("alice") && !debug.contains("secret"));
+        assert!(!format!("{:?}", Auth::bearer("t0k3n")).contains("t0k3n"));
+    }
+}
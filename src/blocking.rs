@@ -0,0 +1,88 @@
+//! Running requests of the blocking clients generated with
+//! `savon::gen::Builder::blocking_client`.
+
+use std::future::Future;
+use std::sync::Arc;
+use std::task::{Context, Poll, Wake, Waker};
+use std::thread::{self, Thread};
+
+struct ThreadWaker(Thread);
+
+impl Wake for ThreadWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
+    }
+}
+
+/// Run a future to completion on the current thread.
+///
+/// Blocking transports, like `reqwest::blocking::Client` or `MockTransport`, send the
+/// request as the future is polled, without a runtime. Futures that need one, like those
+/// of the async `reqwest::Client`, can't be run this way: tokio's panic outside of a
+/// runtime, others may never be woken and block the thread forever. This includes the
+/// futures of a `TokenProvider` given to a blocking client.
+pub fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = Box::pin(future);
+    let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+    let mut context = Context::from_waker(&waker);
+    loop {
+        match future.as_mut().poll(&mut context) {
+            Poll::Ready(output) => return output,
+            Poll::Pending => thread::park(),
+        }
+    }
+}
+
+/// Keeps items only with the `blocking` feature, like the constructors of generated
+/// blocking clients defaulting to `reqwest::blocking::Client`.
+#[cfg(feature = "blocking")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __savon_with_reqwest_blocking {
+    ($($item:item)*) => {
+        $($item)*
+    };
+}
+
+#[cfg(not(feature = "blocking"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __savon_with_reqwest_blocking {
+    ($($item:item)*) => {};
+}
+
+#[doc(hidden)]
+pub use crate::__savon_with_reqwest_blocking as with_reqwest;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::pin::Pin;
+
+    /// Pending until woken from another thread.
+    struct Woken(Option<thread::JoinHandle<()>>);
+
+    impl Future for Woken {
+        type Output = &'static str;
+
+        fn poll(mut self: Pin<&mut Self>, context: &mut Context) -> Poll<Self::Output> {
+            match self.0.take() {
+                Some(handle) => {
+                    handle.join().unwrap();
+                    Poll::Ready("done")
+                }
+                None => {
+                    let waker = context.waker().clone();
+                    self.0 = Some(thread::spawn(move || waker.wake()));
+                    Poll::Pending
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn runs_futures_to_completion() {
+        assert_eq!(block_on(async { 42 }), 42);
+        assert_eq!(block_on(Woken(None)), "done");
+    }
+}
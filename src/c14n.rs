@@ -0,0 +1,324 @@
+//! Exclusive XML canonicalization (exc-c14n, without comments) of `xmltree` elements.
+//!
+//! Namespace declarations are read from the `xmlns` attributes of built elements, and from
+//! the `namespaces` of parsed ones. `Element::parse` drops the prefixes of attributes and
+//! the whitespace between elements, so documents to verify are read with `parse` instead.
+
+use std::collections::BTreeMap;
+
+use xml::reader::{EventReader, ParserConfig, XmlEvent};
+use xmltree::{Element, ParseError, XMLNode};
+
+/// Namespaces in scope by prefix, the empty prefix standing for the default namespace.
+pub type Scope = BTreeMap<String, String>;
+
+const XML_NS: &str = "http://www.w3.org/XML/1998/namespace";
+
+/// Parse a document, keeping attribute prefixes and whitespace text.
+///
+/// Attributes are keyed by their qualified name, like `wsu:Id`.
+pub fn parse(xml: &[u8]) -> Result<Element, ParseError> {
+    let config = ParserConfig::new()
+        .whitespace_to_characters(true)
+        .cdata_to_characters(true)
+        .ignore_comments(true)
+        .coalesce_characters(true);
+    let mut reader = EventReader::new_with_config(xml, config);
+    let mut stack: Vec<Element> = vec![];
+
+    loop {
+        match reader.next().map_err(ParseError::MalformedXml)? {
+            XmlEvent::StartElement {
+                name,
+                attributes,
+                namespace,
+            } => {
+                let mut element = Element::new(&name.local_name);
+                element.prefix = name.prefix;
+                element.namespace = name.namespace;
+                element.namespaces = Some(namespace);
+                for attribute in attributes {
+                    let key = match attribute.name.prefix {
+                        Some(prefix) => format!("{}:{}", prefix, attribute.name.local_name),
+                        None => attribute.name.local_name,
+                    };
+                    element.attributes.insert(key, attribute.value);
+                }
+                stack.push(element);
+            }
+            XmlEvent::EndElement { .. } => {
+                let element = stack.pop().ok_or(ParseError::CannotParse)?;
+                match stack.last_mut() {
+                    Some(parent) => parent.children.push(XMLNode::Element(element)),
+                    None => return Ok(element),
+                }
+            }
+            XmlEvent::Characters(text) | XmlEvent::Whitespace(text) | XmlEvent::CData(text) => {
+                if let Some(parent) = stack.last_mut() {
+                    parent.children.push(XMLNode::Text(text));
+                }
+            }
+            XmlEvent::ProcessingInstruction { name, data } => {
+                if let Some(parent) = stack.last_mut() {
+                    parent
+                        .children
+                        .push(XMLNode::ProcessingInstruction(name, data));
+                }
+            }
+            XmlEvent::EndDocument => return Err(ParseError::CannotParse),
+            _ => {}
+        }
+    }
+}
+
+/// Canonical form of a root element.
+pub fn canonicalize(element: &Element) -> String {
+    canonicalize_in(element, &Scope::new(), &[])
+}
+
+/// Canonical form of an element, given the namespaces declared by its ancestors.
+///
+/// Prefixes of `inclusive_prefixes` (the `InclusiveNamespaces PrefixList`, with `#default`
+/// for the default namespace) are rendered whenever they are in scope, rather than only
+/// where they are used.
+pub fn canonicalize_in(element: &Element, scope: &Scope, inclusive_prefixes: &[&str]) -> String {
+    let mut out = String::new();
+    write_element(element, scope, &Scope::new(), inclusive_prefixes, &mut out);
+    out
+}
+
+/// Namespaces in scope within an element, adding its declarations to those of its parent.
+pub fn scope(element: &Element, parent: &Scope) -> Scope {
+    let mut scope = parent.clone();
+    if let Some(namespaces) = element.namespaces.as_ref() {
+        for (prefix, uri) in namespaces {
+            if prefix != "xml" && prefix != "xmlns" {
+                scope.insert(prefix.to_string(), uri.to_string());
+            }
+        }
+    }
+    for (key, uri) in element.attributes.iter() {
+        if key == "xmlns" {
+            scope.insert(String::new(), uri.clone());
+        } else if let Some(prefix) = key.strip_prefix("xmlns:") {
+            scope.insert(prefix.to_string(), uri.clone());
+        }
+    }
+    scope
+}
+
+/// Prefix and local name of an element, whether the prefix is set or part of the name.
+pub fn qualified_name(element: &Element) -> (&str, &str) {
+    match element.prefix.as_deref() {
+        Some(prefix) => (prefix, &element.name),
+        None => element.name.split_once(':').unwrap_or(("", &element.name)),
+    }
+}
+
+/// Namespace of an element, looking its prefix up in scope.
+pub fn namespace_of<'a>(element: &'a Element, scope: &'a Scope) -> Option<&'a str> {
+    let (prefix, _) = qualified_name(element);
+    scope
+        .get(prefix)
+        .map(|uri| uri.as_str())
+        .or(element.namespace.as_deref())
+        .filter(|uri| !uri.is_empty())
+}
+
+fn write_element(
+    element: &Element,
+    parent_scope: &Scope,
+    rendered: &Scope,
+    inclusive_prefixes: &[&str],
+    out: &mut String,
+) {
+    let scope = scope(element, parent_scope);
+    let (prefix, local_name) = qualified_name(element);
+
+    // namespaces visibly used by the element and its attributes
+    let mut used = Scope::new();
+    used.insert(
+        prefix.to_string(),
+        namespace_of(element, &scope)
+            .unwrap_or_default()
+            .to_string(),
+    );
+    let mut attributes = vec![];
+    for (key, value) in element.attributes.iter() {
+        if key == "xmlns" || key.starts_with("xmlns:") {
+            continue;
+        }
+        let (uri, name) = match key.split_once(':') {
+            Some(("xml", name)) => (XML_NS.to_string(), name),
+            Some((prefix, name)) => {
+                let uri = scope.get(prefix).cloned().unwrap_or_default();
+                used.insert(prefix.to_string(), uri.clone());
+                (uri, name)
+            }
+            None => (String::new(), key.as_str()),
+        };
+        attributes.push((uri, name, key, value));
+    }
+    for prefix in inclusive_prefixes {
+        let prefix = if *prefix == "#default" { "" } else { prefix };
+        if let Some(uri) = scope.get(prefix) {
+            used.insert(prefix.to_string(), uri.clone());
+        }
+    }
+
+    let mut rendered = rendered.clone();
+    out.push('<');
+    out.push_str(&element_name(prefix, local_name));
+    // prefixes sort with the default namespace first
+    for (prefix, uri) in used {
+        let current = rendered
+            .get(&prefix)
+            .map(|u| u.as_str())
+            .unwrap_or_default();
+        if current == uri {
+            continue;
+        }
+        if prefix.is_empty() {
+            out.push_str(" xmlns=\"");
+        } else {
+            out.push_str(" xmlns:");
+            out.push_str(&prefix);
+            out.push_str("=\"");
+        }
+        escape_attribute(&uri, out);
+        out.push('"');
+        rendered.insert(prefix, uri);
+    }
+
+    attributes.sort_by(|a, b| (&a.0, a.1).cmp(&(&b.0, b.1)));
+    for (_, _, key, value) in attributes {
+        out.push(' ');
+        out.push_str(key);
+        out.push_str("=\"");
+        escape_attribute(value, out);
+        out.push('"');
+    }
+    out.push('>');
+
+    for child in element.children.iter() {
+        match child {
+            XMLNode::Element(child) => {
+                write_element(child, &scope, &rendered, inclusive_prefixes, out)
+            }
+            XMLNode::Text(text) | XMLNode::CData(text) => escape_text(text, out),
+            XMLNode::ProcessingInstruction(target, data) => {
+                out.push_str("<?");
+                out.push_str(target);
+                if let Some(data) = data {
+                    out.push(' ');
+                    out.push_str(data);
+                }
+                out.push_str("?>");
+            }
+            XMLNode::Comment(_) => {}
+        }
+    }
+
+    out.push_str("</");
+    out.push_str(&element_name(prefix, local_name));
+    out.push('>');
+}
+
+fn element_name(prefix: &str, local_name: &str) -> String {
+    if prefix.is_empty() {
+        local_name.to_string()
+    } else {
+        format!("{}:{}", prefix, local_name)
+    }
+}
+
+fn escape_text(text: &str, out: &mut String) {
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '\r' => out.push_str("&#xD;"),
+            c => out.push(c),
+        }
+    }
+}
+
+fn escape_attribute(value: &str, out: &mut String) {
+    for c in value.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '"' => out.push_str("&quot;"),
+            '\t' => out.push_str("&#x9;"),
+            '\n' => out.push_str("&#xA;"),
+            '\r' => out.push_str("&#xD;"),
+            c => out.push(c),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rpser::xml::BuildElement;
+    use crate::rpser::Method;
+
+    #[test]
+    fn renders_namespaces_where_used() {
+        // example of the exclusive canonicalization recommendation
+        let xml = r#"<n0:local xmlns:n0="foo:bar" xmlns:n3="ftp://example.org"><n1:elem2 xmlns:n1="http://example.net" xml:lang="en"><n3:stuff xmlns:n3="ftp://example.org"/></n1:elem2></n0:local>"#;
+        let document = parse(xml.as_bytes()).unwrap();
+        let elem2 = document.get_child("elem2").unwrap();
+        assert_eq!(
+            canonicalize_in(elem2, &Scope::new(), &[]),
+            r#"<n1:elem2 xmlns:n1="http://example.net" xml:lang="en"><n3:stuff xmlns:n3="ftp://example.org"></n3:stuff></n1:elem2>"#
+        );
+        assert_eq!(
+            canonicalize_in(elem2, &Scope::new(), &["n0"]),
+            r#"<n1:elem2 xmlns:n0="foo:bar" xmlns:n1="http://example.net" xml:lang="en"><n3:stuff xmlns:n3="ftp://example.org"></n3:stuff></n1:elem2>"#
+        );
+    }
+
+    #[test]
+    fn sorts_attributes_and_escapes() {
+        let xml = "<?xml version=\"1.0\"?>\n<doc xmlns=\"urn:doc\" xmlns:b=\"urn:b\" xmlns:a=\"urn:a\">\n  <!-- dropped -->\n  <e z=\"1\" b:y=\"2\" a:y=\"3\" a=\"&quot;&#9;\"><![CDATA[a < b & c]]></e>\n  <f xmlns=\"\"/>\n</doc>";
+        assert_eq!(
+            canonicalize(&parse(xml.as_bytes()).unwrap()),
+            "<doc xmlns=\"urn:doc\">\n  \n  <e xmlns:a=\"urn:a\" xmlns:b=\"urn:b\" a=\"&quot;&#x9;\" z=\"1\" a:y=\"3\" b:y=\"2\">a &lt; b &amp; c</e>\n  <f xmlns=\"\"></f>\n</doc>"
+        );
+    }
+
+    #[test]
+    fn built_and_parsed_envelopes_agree() {
+        let envelope = Method::new("GetPrice")
+            .with_header(
+                Element::node("Session")
+                    .with_namespace("urn:session")
+                    .with_qualified_attr("urn:meta", "scope", "all")
+                    .with_text("s3cr3t"),
+            )
+            .with(Element::node("ticker").with_text("SOAP & co"))
+            .envelope("http://example.com/stock.wsdl");
+        let parsed = parse(envelope.to_string().as_bytes()).unwrap();
+
+        let scope = scope(&envelope, &Scope::new());
+        for name in ["Header", "Body"] {
+            let built = envelope
+                .children
+                .iter()
+                .filter_map(|c| c.as_element())
+                .find(|e| qualified_name(e).1 == name)
+                .unwrap();
+            assert_eq!(namespace_of(built, &scope), parsed.namespace.as_deref());
+            assert_eq!(
+                canonicalize_in(built, &scope, &[]),
+                canonicalize(parsed.get_child(name).unwrap())
+            );
+        }
+        assert_eq!(
+            canonicalize(parsed.get_child("Header").unwrap()),
+            r#"<soap:Header xmlns:soap="http://schemas.xmlsoap.org/soap/envelope/"><ns1:Session xmlns:ns1="urn:session" xmlns:ns2="urn:meta" ns2:scope="all">s3cr3t</ns1:Session></soap:Header>"#
+        );
+    }
+}
@@ -0,0 +1,678 @@
+//! XML signatures of WS-Security messages: signing the Body, Timestamp and WS-Addressing
+//! headers of requests with an X.509 certificate, and verifying the signature of responses.
+//!
+//! Only exclusive canonicalization, RSA-SHA256 signatures and SHA-256 digests are supported.
+
+use std::collections::HashMap;
+use std::fmt::{self, Debug};
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use chrono::{DateTime, Duration, Utc};
+use rsa::pkcs1::DecodeRsaPrivateKey;
+use rsa::pkcs8::{DecodePrivateKey, DecodePublicKey};
+use rsa::{Pkcs1v15Sign, RsaPrivateKey, RsaPublicKey};
+use sha2::{Digest, Sha256};
+use x509_cert::der::{DecodePem, Encode};
+use x509_cert::Certificate;
+use xmltree::{Element, XMLNode};
+
+use crate::c14n::{self, Scope};
+use crate::multipart::unique_id;
+use crate::rpser::xml::BuildElement;
+use crate::rpser::{SOAP12_ENVELOPE_NS, SOAP_ENVELOPE_NS};
+use crate::wsa::{WSA_NS, WSA_SUBMISSION_NS};
+use crate::wsse::{BASE64_BINARY, WSSE_NS, WSU_NS};
+
+/// XML signature namespace.
+pub const DS_NS: &str = "http://www.w3.org/2000/09/xmldsig#";
+
+const EXC_C14N: &str = "http://www.w3.org/2001/10/xml-exc-c14n#";
+const RSA_SHA256: &str = "http://www.w3.org/2001/04/xmldsig-more#rsa-sha256";
+const SHA256: &str = "http://www.w3.org/2001/04/xmlenc#sha256";
+const X509V3: &str =
+    "http://docs.oasis-open.org/wss/2004/01/oasis-200401-wss-x509-token-profile-1.0#X509v3";
+/// Difference allowed between the clocks of the server and the client.
+const CLOCK_SKEW_SECONDS: i64 = 300;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Error {
+    /// Private key or certificate could not be read.
+    InvalidKey { reason: String },
+    /// Response is not well formed XML.
+    InvalidXml { reason: String },
+    /// Envelope lacks an element needed to sign or verify it.
+    MissingElement { name: String },
+    /// Signature uses an algorithm other than exc-c14n, RSA-SHA256 or SHA-256.
+    UnsupportedAlgorithm { algorithm: String },
+    /// Reference URI doesn't point to an element of the envelope.
+    UnknownReference { uri: String },
+    /// Referenced element was modified after being signed.
+    DigestMismatch { uri: String },
+    /// Signature value doesn't match the signed info and the trusted key.
+    InvalidSignature,
+    /// Body of the response is not among the signed references.
+    UnsignedBody,
+    /// Several elements have the same Id, so a reference could point to a copy.
+    DuplicateId { id: String },
+    /// Reference points to a Body or Timestamp other than those of the envelope and its
+    /// Security header, like a signed copy moved elsewhere (signature wrapping).
+    MisplacedReference { uri: String },
+    /// Timestamp of the Security header is not among the signed references.
+    UnsignedTimestamp,
+    /// Timestamp of the response is not yet valid or has expired, beyond the clock skew.
+    Expired,
+}
+
+/// Signs requests with a private key, sending its certificate as a `BinarySecurityToken`.
+#[derive(Clone)]
+pub struct Signer {
+    key: RsaPrivateKey,
+    /// DER encoded certificate.
+    certificate: Vec<u8>,
+}
+
+impl Debug for Signer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Signer").finish_non_exhaustive()
+    }
+}
+
+impl Signer {
+    /// Load a PEM private key, in PKCS#8 or PKCS#1 format, and its PEM certificate.
+    pub fn from_pem(key: &str, certificate: &str) -> Result<Signer, Error> {
+        let key = RsaPrivateKey::from_pkcs8_pem(key)
+            .or_else(|_| RsaPrivateKey::from_pkcs1_pem(key))
+            .map_err(invalid_key)?;
+        let certificate = Certificate::from_pem(certificate)
+            .and_then(|c| c.to_der())
+            .map_err(invalid_key)?;
+        Ok(Signer { key, certificate })
+    }
+
+    /// Sign the Body, the `wsu:Timestamp` and the WS-Addressing headers of an envelope built
+    /// by `savon::rpser::Method::envelope`, adding the certificate and the signature to its
+    /// `wsse:Security` header.
+    pub fn sign(&self, envelope: &mut Element) -> Result<(), Error> {
+        let wsu = declare(envelope, WSU_NS, "wsu");
+        let wsse = declare(envelope, WSSE_NS, "wsse");
+        let ds = declare(envelope, DS_NS, "ds");
+        let id_key = format!("{}:Id", wsu);
+        let scope = c14n::scope(envelope, &Scope::new());
+
+        let mut references = vec![];
+        let body = child_mut(envelope, "Body", SOAP_ENVELOPE_NS)?;
+        let body_id = format!("Body-{}", unique_id());
+        body.attributes.insert(id_key.clone(), body_id.clone());
+        references.push((body_id, digest(body, &scope, &[])));
+
+        let header = child_mut(envelope, "Header", SOAP_ENVELOPE_NS)?;
+        let scope = c14n::scope(header, &scope);
+        // so the request cannot be replayed to another action or endpoint
+        for addressing in header
+            .children
+            .iter_mut()
+            .filter_map(|c| c.as_mut_element())
+            .filter(|e| {
+                matches!(
+                    e.namespace.as_deref(),
+                    Some(WSA_NS) | Some(WSA_SUBMISSION_NS)
+                )
+            })
+        {
+            let id = format!("{}-{}", addressing.name, unique_id());
+            addressing.attributes.insert(id_key.clone(), id.clone());
+            references.push((id, digest(addressing, &scope, &[])));
+        }
+        let security = child_mut(header, "Security", WSSE_NS)?;
+        let scope = c14n::scope(security, &scope);
+        if let Ok(timestamp) = child_mut(security, "Timestamp", WSU_NS) {
+            let timestamp_id = format!("Timestamp-{}", unique_id());
+            timestamp
+                .attributes
+                .insert(id_key.clone(), timestamp_id.clone());
+            references.push((timestamp_id, digest(timestamp, &scope, &[])));
+        }
+
+        let token_id = format!("X509-{}", unique_id());
+        let token = Element::node(format!("{}:BinarySecurityToken", wsse))
+            .with_attr("EncodingType", BASE64_BINARY)
+            .with_attr("ValueType", X509V3)
+            .with_attr(id_key, token_id.clone())
+            .with_text(STANDARD.encode(&self.certificate));
+
+        let ds_node = |name: &str| Element::node(format!("{}:{}", ds, name));
+        let algorithm =
+            |name: &str, algorithm: &str| ds_node(name).with_attr("Algorithm", algorithm);
+        let signed_info = ds_node("SignedInfo")
+            .with_children(vec![
+                algorithm("CanonicalizationMethod", EXC_C14N),
+                algorithm("SignatureMethod", RSA_SHA256),
+            ])
+            .with_children(references.into_iter().map(|(id, digest)| {
+                ds_node("Reference")
+                    .with_attr("URI", format!("#{}", id))
+                    .with_children(vec![
+                        ds_node("Transforms").with_child(algorithm("Transform", EXC_C14N)),
+                        algorithm("DigestMethod", SHA256),
+                        ds_node("DigestValue").with_text(digest),
+                    ])
+            }));
+
+        // the signature element declares nothing, so it shares the scope of the header
+        let hashed = Sha256::digest(c14n::canonicalize_in(&signed_info, &scope, &[]));
+        let signature_value = self
+            .key
+            .sign(Pkcs1v15Sign::new::<Sha256>(), &hashed)
+            .map_err(invalid_key)?;
+
+        let signature = ds_node("Signature").with_children(vec![
+            signed_info,
+            ds_node("SignatureValue").with_text(STANDARD.encode(signature_value)),
+            ds_node("KeyInfo").with_child(
+                Element::node(format!("{}:SecurityTokenReference", wsse)).with_child(
+                    Element::node(format!("{}:Reference", wsse))
+                        .with_attr("URI", format!("#{}", token_id))
+                        .with_attr("ValueType", X509V3),
+                ),
+            ),
+        ]);
+        security.children.push(XMLNode::Element(token));
+        security.children.push(XMLNode::Element(signature));
+        Ok(())
+    }
+}
+
+/// Verifies the signature of responses against a trusted certificate.
+///
+/// Certificates sent in the response are ignored, as anyone could sign with their own.
+#[derive(Clone, Debug)]
+pub struct Verifier {
+    key: RsaPublicKey,
+}
+
+impl Verifier {
+    /// Trust the key of a PEM certificate.
+    pub fn from_pem(certificate: &str) -> Result<Verifier, Error> {
+        let certificate = Certificate::from_pem(certificate).map_err(invalid_key)?;
+        let public_key = certificate
+            .tbs_certificate
+            .subject_public_key_info
+            .to_der()
+            .map_err(invalid_key)?;
+        let key = RsaPublicKey::from_public_key_der(&public_key).map_err(invalid_key)?;
+        Ok(Verifier { key })
+    }
+
+    /// Check the `ds:Signature` of the `wsse:Security` header of a response envelope.
+    ///
+    /// Every reference must match its element, and the Body must be among them. References
+    /// must point to the Body of the envelope and the Timestamp of its Security header, not
+    /// to copies elsewhere. When the Security header has a Timestamp, it must be signed too,
+    /// and the response must be received between its Created and Expires times.
+    pub fn verify(&self, xml: &[u8]) -> Result<(), Error> {
+        self.verify_at(xml, Utc::now())
+    }
+
+    fn verify_at(&self, xml: &[u8], now: DateTime<Utc>) -> Result<(), Error> {
+        let envelope = c14n::parse(xml).map_err(|e| Error::InvalidXml {
+            reason: e.to_string(),
+        })?;
+        let soap = match envelope.namespace.as_deref() {
+            Some(ns @ SOAP_ENVELOPE_NS) | Some(ns @ SOAP12_ENVELOPE_NS)
+                if envelope.name == "Envelope" =>
+            {
+                ns
+            }
+            _ => return Err(missing("Envelope")),
+        };
+        let security = child(&envelope, "Header", soap)
+            .and_then(|header| child(header, "Security", WSSE_NS))?;
+        let signature = child(security, "Signature", DS_NS)?;
+        let signed_info = child(signature, "SignedInfo", DS_NS)?;
+
+        let canonicalization = child(signed_info, "CanonicalizationMethod", DS_NS)?;
+        check_algorithm(canonicalization, EXC_C14N)?;
+        check_algorithm(child(signed_info, "SignatureMethod", DS_NS)?, RSA_SHA256)?;
+
+        let bodies = envelope.find_children("Body", Some(soap));
+        let body = match bodies.as_slice() {
+            [body] => *body,
+            [] => return Err(missing("Body")),
+            _ => {
+                return Err(Error::InvalidXml {
+                    reason: "several Body elements".to_string(),
+                })
+            }
+        };
+        let timestamp = security.find_child("Timestamp", Some(WSU_NS));
+        let mut elements = HashMap::new();
+        collect_ids(&envelope, &mut elements)?;
+
+        let mut body_signed = false;
+        let mut timestamp_signed = false;
+        for reference in signed_info.find_children("Reference", Some(DS_NS)) {
+            let uri = reference.attributes.get("URI").cloned().unwrap_or_default();
+            let id = uri.strip_prefix('#').unwrap_or_default();
+            let element = *elements
+                .get(id)
+                .ok_or_else(|| Error::UnknownReference { uri: uri.clone() })?;
+            let misplaced = if element.matches("Body", Some(soap)) {
+                !std::ptr::eq(element, body)
+            } else if element.matches("Timestamp", Some(WSU_NS)) {
+                !timestamp.is_some_and(|t| std::ptr::eq(element, t))
+            } else {
+                false
+            };
+            if misplaced {
+                return Err(Error::MisplacedReference { uri });
+            }
+
+            let mut inclusive_prefixes = vec![];
+            if let Some(transforms) = reference.find_child("Transforms", Some(DS_NS)) {
+                for transform in transforms.find_children("Transform", Some(DS_NS)) {
+                    check_algorithm(transform, EXC_C14N)?;
+                    inclusive_prefixes.extend(prefix_list(transform));
+                }
+            }
+            check_algorithm(child(reference, "DigestMethod", DS_NS)?, SHA256)?;
+
+            let expected = decode(&text(child(reference, "DigestValue", DS_NS)?));
+            let prefixes = inclusive_prefixes
+                .iter()
+                .map(|p| p.as_str())
+                .collect::<Vec<_>>();
+            // parsed elements carry every namespace in scope
+            let actual = Sha256::digest(c14n::canonicalize_in(element, &Scope::new(), &prefixes));
+            if expected.as_deref() != Some(actual.as_slice()) {
+                return Err(Error::DigestMismatch { uri });
+            }
+            body_signed |= std::ptr::eq(element, body);
+            timestamp_signed |= timestamp.is_some_and(|t| std::ptr::eq(element, t));
+        }
+        if !body_signed {
+            return Err(Error::UnsignedBody);
+        }
+        if timestamp.is_some() && !timestamp_signed {
+            return Err(Error::UnsignedTimestamp);
+        }
+
+        let prefixes = prefix_list(canonicalization);
+        let prefixes = prefixes.iter().map(|p| p.as_str()).collect::<Vec<_>>();
+        let hashed = Sha256::digest(c14n::canonicalize_in(signed_info, &Scope::new(), &prefixes));
+        let signature_value = decode(&text(child(signature, "SignatureValue", DS_NS)?))
+            .ok_or(Error::InvalidSignature)?;
+        self.key
+            .verify(Pkcs1v15Sign::new::<Sha256>(), &hashed, &signature_value)
+            .map_err(|_| Error::InvalidSignature)?;
+
+        match timestamp {
+            Some(timestamp) => check_timestamp(timestamp, now),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Check that `now` is between the Created and Expires times of a `wsu:Timestamp`.
+fn check_timestamp(timestamp: &Element, now: DateTime<Utc>) -> Result<(), Error> {
+    let time = |name| {
+        timestamp
+            .find_child(name, Some(WSU_NS))
+            .map(|element| {
+                DateTime::parse_from_rfc3339(&text(element)).map_err(|e| Error::InvalidXml {
+                    reason: format!("{} of the Timestamp: {}", name, e),
+                })
+            })
+            .transpose()
+    };
+    let skew = Duration::seconds(CLOCK_SKEW_SECONDS);
+    let not_yet = time("Created")?.is_some_and(|created| created > now + skew);
+    let expired = time("Expires")?.is_some_and(|expires| expires <= now - skew);
+    if not_yet || expired {
+        return Err(Error::Expired);
+    }
+    Ok(())
+}
+
+fn invalid_key(e: impl fmt::Display) -> Error {
+    Error::InvalidKey {
+        reason: e.to_string(),
+    }
+}
+
+fn missing(name: &str) -> Error {
+    Error::MissingElement {
+        name: name.to_string(),
+    }
+}
+
+/// Prefix of a namespace declared on the envelope, declaring it if needed.
+fn declare(envelope: &mut Element, uri: &str, prefix: &str) -> String {
+    let declared = envelope
+        .attributes
+        .iter()
+        .find(|(key, value)| key.starts_with("xmlns:") && *value == uri)
+        .map(|(key, _)| key["xmlns:".len()..].to_string());
+    declared.unwrap_or_else(|| {
+        envelope
+            .attributes
+            .insert(format!("xmlns:{}", prefix), uri.to_string());
+        prefix.to_string()
+    })
+}
+
+fn child_mut<'a>(
+    element: &'a mut Element,
+    name: &str,
+    namespace: &str,
+) -> Result<&'a mut Element, Error> {
+    element
+        .children
+        .iter_mut()
+        .filter_map(|c| c.as_mut_element())
+        .find(|e| e.matches(name, Some(namespace)))
+        .ok_or_else(|| missing(name))
+}
+
+fn child<'a>(element: &'a Element, name: &str, namespace: &str) -> Result<&'a Element, Error> {
+    element
+        .find_child(name, Some(namespace))
+        .ok_or_else(|| missing(name))
+}
+
+fn check_algorithm(element: &Element, expected: &str) -> Result<(), Error> {
+    match element.attributes.get("Algorithm") {
+        Some(algorithm) if algorithm == expected => Ok(()),
+        algorithm => Err(Error::UnsupportedAlgorithm {
+            algorithm: algorithm.cloned().unwrap_or_default(),
+        }),
+    }
+}
+
+/// Prefixes of the `InclusiveNamespaces` of a canonicalization method or transform.
+fn prefix_list(algorithm: &Element) -> Vec<String> {
+    algorithm
+        .find_child("InclusiveNamespaces", Some(EXC_C14N))
+        .and_then(|e| e.attributes.get("PrefixList"))
+        .map(|list| list.split_whitespace().map(|p| p.to_string()).collect())
+        .unwrap_or_default()
+}
+
+/// Value of an attribute by local name, whatever its prefix.
+fn attribute(element: &Element, name: &str) -> Option<String> {
+    element
+        .attributes
+        .iter()
+        .find(|(key, _)| key.rsplit(':').next() == Some(name))
+        .map(|(_, value)| value.clone())
+}
+
+/// Elements by their `Id` or `ID` attribute, rejecting duplicates.
+fn collect_ids<'a>(
+    element: &'a Element,
+    elements: &mut HashMap<String, &'a Element>,
+) -> Result<(), Error> {
+    for name in ["Id", "ID"] {
+        if let Some(id) = attribute(element, name) {
+            if elements.insert(id.clone(), element).is_some() {
+                return Err(Error::DuplicateId { id });
+            }
+        }
+    }
+    element
+        .children
+        .iter()
+        .filter_map(|c| c.as_element())
+        .try_for_each(|child| collect_ids(child, elements))
+}
+
+fn text(element: &Element) -> String {
+    element
+        .get_text()
+        .map(|t| t.split_whitespace().collect())
+        .unwrap_or_default()
+}
+
+fn decode(text: &str) -> Option<Vec<u8>> {
+    STANDARD.decode(text).ok()
+}
+
+fn digest(element: &Element, scope: &Scope, inclusive_prefixes: &[&str]) -> String {
+    STANDARD.encode(Sha256::digest(c14n::canonicalize_in(
+        element,
+        scope,
+        inclusive_prefixes,
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rpser::Method;
+    use crate::wsse::{PasswordType, Security};
+    use chrono::Duration;
+
+    const KEY: &str = include_str!("../assets/test-key.pem");
+    const CERTIFICATE: &str = include_str!("../assets/test-cert.pem");
+    const OTHER_CERTIFICATE: &str = include_str!("../assets/other-cert.pem");
+
+    fn signed_envelope() -> String {
+        let security = Security::username_token("alice", "secret", PasswordType::Text)
+            .with_timestamp(Duration::minutes(5));
+        let mut envelope = Method::new("GetPrice")
+            .with_header(security.to_element())
+            .with(Element::node("ticker").with_text("SOAP"))
+            .envelope("http://example.com/stock.wsdl");
+        Signer::from_pem(KEY, CERTIFICATE)
+            .unwrap()
+            .sign(&mut envelope)
+            .unwrap();
+        envelope.to_string()
+    }
+
+    #[test]
+    fn signs_body_and_timestamp() {
+        let xml = signed_envelope();
+        let envelope = c14n::parse(xml.as_bytes()).unwrap();
+        let security = envelope
+            .get_child("Header")
+            .and_then(|h| h.get_child("Security"))
+            .unwrap();
+
+        let token = child(security, "BinarySecurityToken", WSSE_NS).unwrap();
+        assert_eq!(token.attributes["ValueType"], X509V3);
+        let certificate = Certificate::from_pem(CERTIFICATE).unwrap();
+        assert_eq!(decode(&text(token)).unwrap(), certificate.to_der().unwrap());
+
+        let signature = child(security, "Signature", DS_NS).unwrap();
+        let references = child(signature, "SignedInfo", DS_NS)
+            .unwrap()
+            .find_children("Reference", Some(DS_NS))
+            .into_iter()
+            .map(|r| r.attributes["URI"].clone())
+            .collect::<Vec<_>>();
+        let body = envelope.get_child("Body").unwrap();
+        let timestamp = security.get_child("Timestamp").unwrap();
+        assert_eq!(
+            references,
+            [
+                format!("#{}", attribute(body, "Id").unwrap()),
+                format!("#{}", attribute(timestamp, "Id").unwrap()),
+            ]
+        );
+        let reference = signature
+            .get_child("KeyInfo")
+            .and_then(|k| k.get_child("SecurityTokenReference"))
+            .and_then(|s| s.get_child("Reference"))
+            .unwrap();
+        assert_eq!(
+            reference.attributes["URI"],
+            format!("#{}", attribute(token, "Id").unwrap())
+        );
+
+        let verifier = Verifier::from_pem(CERTIFICATE).unwrap();
+        assert_eq!(verifier.verify(xml.as_bytes()), Ok(()));
+    }
+
+    #[test]
+    fn signs_addressing_headers() {
+        let addressing = crate::wsa::Addressing::new("urn:GetPrice", "http://localhost/stock");
+        let mut method = Method::new("GetPrice")
+            .with_header(Security::default().to_element())
+            .with(Element::node("ticker").with_text("SOAP"));
+        method.headers.extend(addressing.to_elements());
+        let mut envelope = method.envelope("http://example.com/stock.wsdl");
+        Signer::from_pem(KEY, CERTIFICATE)
+            .unwrap()
+            .sign(&mut envelope)
+            .unwrap();
+        let xml = envelope.to_string();
+
+        let envelope = c14n::parse(xml.as_bytes()).unwrap();
+        let references = envelope
+            .get_child("Header")
+            .and_then(|h| h.get_child("Security"))
+            .and_then(|s| s.get_child("Signature"))
+            .and_then(|s| s.get_child("SignedInfo"))
+            .unwrap()
+            .find_children("Reference", Some(DS_NS))
+            .into_iter()
+            .map(|r| r.attributes["URI"].clone())
+            .collect::<Vec<_>>();
+        assert_eq!(references.len(), 5);
+        assert!(references[1].starts_with("#Action-"));
+        assert!(references[4].starts_with("#To-"));
+
+        let verifier = Verifier::from_pem(CERTIFICATE).unwrap();
+        assert_eq!(verifier.verify(xml.as_bytes()), Ok(()));
+        let redirected = xml.replace(">urn:GetPrice<", ">urn:DeleteAll<");
+        assert!(matches!(
+            verifier.verify(redirected.as_bytes()),
+            Err(Error::DigestMismatch { uri }) if uri.starts_with("#Action-")
+        ));
+    }
+
+    #[test]
+    fn rejects_tampered_or_foreign_signatures() {
+        let xml = signed_envelope();
+        let verifier = Verifier::from_pem(CERTIFICATE).unwrap();
+
+        let tampered = xml.replace(">SOAP<", ">SOAQ<");
+        assert!(matches!(
+            verifier.verify(tampered.as_bytes()),
+            Err(Error::DigestMismatch { uri }) if uri.starts_with("#Body-")
+        ));
+
+        let other = Verifier::from_pem(OTHER_CERTIFICATE).unwrap();
+        assert_eq!(other.verify(xml.as_bytes()), Err(Error::InvalidSignature));
+
+        let unsigned = Method::new("GetPrice")
+            .with_header(Security::default().to_element())
+            .as_xml("http://example.com/stock.wsdl");
+        assert_eq!(
+            verifier.verify(unsigned.as_bytes()),
+            Err(missing("Signature"))
+        );
+    }
+
+    #[test]
+    fn verifies_reformatted_responses() {
+        // whitespace may change around the signed elements, but not within them
+        let xml = signed_envelope()
+            .replace("<soap:Body ", "\n  <soap:Body ")
+            .replace("</soap:Envelope>", "\n</soap:Envelope>");
+        let verifier = Verifier::from_pem(CERTIFICATE).unwrap();
+        assert_eq!(verifier.verify(xml.as_bytes()), Ok(()));
+
+        let moved = xml.replace("<ns:GetPrice>", "<ns:GetPrice>\n");
+        assert!(matches!(
+            verifier.verify(moved.as_bytes()),
+            Err(Error::DigestMismatch { .. })
+        ));
+
+        assert!(matches!(
+            Signer::from_pem("not a key", CERTIFICATE),
+            Err(Error::InvalidKey { .. })
+        ));
+    }
+
+    #[test]
+    fn rejects_expired_or_unsigned_timestamps() {
+        let xml = signed_envelope();
+        let verifier = Verifier::from_pem(CERTIFICATE).unwrap();
+        let now = Utc::now();
+        assert_eq!(verifier.verify_at(xml.as_bytes(), now), Ok(()));
+        // within the clock skew of the server
+        assert_eq!(
+            verifier.verify_at(xml.as_bytes(), now + Duration::minutes(6)),
+            Ok(())
+        );
+        assert_eq!(
+            verifier.verify_at(xml.as_bytes(), now + Duration::hours(1)),
+            Err(Error::Expired)
+        );
+        assert_eq!(
+            verifier.verify_at(xml.as_bytes(), now - Duration::hours(1)),
+            Err(Error::Expired)
+        );
+
+        // the Timestamp reference removed from the signed info
+        let envelope = c14n::parse(xml.as_bytes()).unwrap();
+        let timestamp = envelope
+            .get_child("Header")
+            .and_then(|h| h.get_child("Security"))
+            .and_then(|s| s.get_child("Timestamp"))
+            .unwrap();
+        let uri = format!("#{}", attribute(timestamp, "Id").unwrap());
+        let start = xml
+            .find(&format!(r#"<ds:Reference URI="{}">"#, uri))
+            .unwrap();
+        let end = start + xml[start..].find("</ds:Reference>").unwrap() + "</ds:Reference>".len();
+        let unsigned = format!("{}{}", &xml[..start], &xml[end..]);
+        assert_eq!(
+            verifier.verify(unsigned.as_bytes()),
+            Err(Error::UnsignedTimestamp)
+        );
+    }
+
+    #[test]
+    fn rejects_wrapped_bodies() {
+        let xml = signed_envelope();
+        let start = xml.find("<soap:Body ").unwrap();
+        let end = xml.find("</soap:Envelope>").unwrap();
+        let body = &xml[start..end];
+        let envelope = c14n::parse(xml.as_bytes()).unwrap();
+        let id = attribute(envelope.get_child("Body").unwrap(), "Id").unwrap();
+        let verifier = Verifier::from_pem(CERTIFICATE).unwrap();
+
+        // the signed Body moved to the Header, and a forged one in its place
+        let wrap = |forged_id: &str| {
+            let forged = format!(
+                r#"<soap:Body ns2:Id="{}"><ns:GetPrice><ticker>EVIL</ticker></ns:GetPrice></soap:Body>"#,
+                forged_id
+            );
+            xml[..start].replace(
+                "<soap:Header>",
+                &format!("<soap:Header><Wrapper>{}</Wrapper>", body),
+            ) + &forged
+                + &xml[end..]
+        };
+        assert_eq!(
+            verifier.verify(wrap(&id).as_bytes()),
+            Err(Error::DuplicateId { id: id.clone() })
+        );
+        assert_eq!(
+            verifier.verify(wrap("forged").as_bytes()),
+            Err(Error::MisplacedReference {
+                uri: format!("#{}", id)
+            })
+        );
+
+        // a second Body next to the signed one
+        let doubled = xml.replace("</soap:Envelope>", "<soap:Body/></soap:Envelope>");
+        assert!(matches!(
+            verifier.verify(doubled.as_bytes()),
+            Err(Error::InvalidXml { .. })
+        ));
+    }
+}
@@ -3,7 +3,21 @@ pub enum Error {
     Wsdl(crate::wsdl::WsdlError),
     Reqwest(reqwest::Error),
     Rpser(crate::rpser::xml::Error),
+    /// Response is not a SOAP envelope, or holds a fault.
+    Rpc(crate::rpser::RpcError),
     Num(std::num::ParseFloatError),
+    Multipart(crate::multipart::Error),
+    Dsig(crate::dsig::Error),
+    /// Credentials could not be obtained, for example by a `savon::auth::TokenProvider`, or
+    /// were rejected by the server.
+    Auth(String),
+    /// Unsuccessful HTTP response without a SOAP fault.
+    Http {
+        status: u16,
+        body: String,
+    },
+    /// Failure of a custom `savon::transport::Transport`.
+    Transport(String),
 }
 
 impl From<crate::wsdl::WsdlError> for Error {
@@ -18,6 +32,12 @@ impl From<crate::rpser::xml::Error> for Error {
     }
 }
 
+impl From<crate::rpser::RpcError> for Error {
+    fn from(e: crate::rpser::RpcError) -> Self {
+        Error::Rpc(e)
+    }
+}
+
 impl From<reqwest::Error> for Error {
     fn from(e: reqwest::Error) -> Self {
         Error::Reqwest(e)
@@ -29,3 +49,15 @@ impl From<std::num::ParseFloatError> for Error {
         Error::Num(e)
     }
 }
+
+impl From<crate::multipart::Error> for Error {
+    fn from(e: crate::multipart::Error) -> Self {
+        Error::Multipart(e)
+    }
+}
+
+impl From<crate::dsig::Error> for Error {
+    fn from(e: crate::dsig::Error) -> Self {
+        Error::Dsig(e)
+    }
+}
@@ -1,69 +1,1066 @@
-use crate::gen::{FromElement, ToElements};
-use crate::rpser::{Method, Response};
-use reqwest::Client;
-use std::fmt::Debug;
+use crate::auth::Auth;
+use crate::gen::{FromElement, FromFault, ToElements};
+use crate::multipart::{self, Part};
+use crate::rpser::xml::BuildElement;
+use crate::rpser::{xop, Method, Response};
+use crate::transport::{HttpRequest, HttpResponse, Transport};
+use crate::{dsig, wsa, wsse};
+use std::fmt::{self, Debug};
+use std::sync::Arc;
+use xmltree::Element;
+
+/// Callback receiving the header elements of a response.
+pub type HeadersCallback = Arc<dyn Fn(&[Element]) + Send + Sync>;
+
+/// Settings applying to every call of a client.
+#[derive(Clone, Default)]
+pub struct Options {
+    /// HTTP authentication of every request.
+    pub auth: Option<Auth>,
+    /// Send large binary values as MTOM attachments.
+    pub mtom: Option<Mtom>,
+    /// WS-Security header added to every request.
+    pub security: Option<wsse::Security>,
+    /// Header elements added to every request, like session tokens or correlation ids.
+    pub headers: Vec<Element>,
+    /// Called with the header elements of every response.
+    pub on_response_headers: Option<HeadersCallback>,
+}
+
+impl Debug for Options {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Options")
+            .field("auth", &self.auth)
+            .field("mtom", &self.mtom)
+            .field("security", &self.security)
+            .field("headers", &self.headers)
+            .field("on_response_headers", &self.on_response_headers.is_some())
+            .finish()
+    }
+}
+
+/// MTOM settings for requests.
+///
+/// Binary values of at least `threshold` bytes are sent as attachments of a
+/// `multipart/related` body, instead of base64 text. MTOM responses are always decoded.
+#[derive(Clone, Debug)]
+pub struct Mtom {
+    pub threshold: usize,
+}
+
+impl Default for Mtom {
+    fn default() -> Self {
+        Mtom { threshold: 1024 }
+    }
+}
+
+/// File sent alongside the envelope, with SOAP with Attachments.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Attachment {
+    /// Content id, without angle brackets.
+    pub content_id: String,
+    pub content_type: String,
+    pub content: Vec<u8>,
+}
+
+impl Attachment {
+    /// Create an attachment for a message part, with a unique content id starting with the
+    /// part name, as the WS-I Attachments Profile requires.
+    pub fn new(part: &str, content_type: &str, content: Vec<u8>) -> Attachment {
+        Attachment {
+            content_id: format!("{}={}@savon", part, multipart::unique_id()),
+            content_type: content_type.to_string(),
+            content,
+        }
+    }
+
+    /// URL referring to the attachment from the envelope, as `cid:<content id>`.
+    pub fn href(&self) -> String {
+        format!("cid:{}", self.content_id)
+    }
+}
+
+/// Header elements and attachments sent with the body of a single request.
+#[derive(Clone, Debug, Default)]
+pub struct Request {
+    pub headers: Vec<Element>,
+    pub attachments: Vec<Attachment>,
+    /// WS-Addressing action, sending `wsa:Action`, `wsa:To`, `wsa:MessageID` and
+    /// `wsa:ReplyTo` headers when set.
+    pub action: Option<String>,
+}
+
+/// Response output, with the header elements and attachments received alongside it.
+#[derive(Clone, Debug)]
+pub struct Reply<Output> {
+    pub output: Output,
+    pub headers: Vec<Element>,
+    pub attachments: Vec<Attachment>,
+    /// Message id of the request, from the `wsa:RelatesTo` header.
+    pub relates_to: Option<String>,
+}
+
+const ROOT_CONTENT_ID: &str = "root.message@savon";
 
 pub async fn one_way<Input: ToElements>(
-    client: &Client,
+    transport: &dyn Transport,
     base_url: &str,
+    options: &Options,
     ns: &str,
     method: &str,
     input: &Input,
 ) -> Result<(), crate::Error> {
-    let mut v = input.to_elements();
-    let mut m = Method::new(method);
-
-    for el in v.drain(..) {
-        m = m.with(el);
-    }
-    let s = m.as_xml(ns);
-    trace!("sending: {}", s);
-
-    let response: String = client
-        .post(base_url)
-        .header("Content-Type", "text/xml")
-        .header("MessageType", "Call")
-        .body(s)
-        .send()
-        .await?
-        .text()
-        .await?;
-
-    trace!("received: {}", response);
+    one_way_with(
+        transport,
+        base_url,
+        options,
+        ns,
+        method,
+        input,
+        &Request::default(),
+    )
+    .await
+}
+
+/// Like `one_way`, sending header elements and attachments with the body.
+pub async fn one_way_with<Input: ToElements>(
+    transport: &dyn Transport,
+    base_url: &str,
+    options: &Options,
+    ns: &str,
+    method: &str,
+    input: &Input,
+    request: &Request,
+) -> Result<(), crate::Error> {
+    let (content_type, body) = request_body(base_url, ns, method, input, options, request)?;
+
+    let response = send(transport, base_url, options, &content_type, body).await?;
+
+    trace!("received: {}", String::from_utf8_lossy(&response.body));
+    if !is_success(response.status) {
+        return Err(status_error(&response));
+    }
     Ok(())
 }
 
-pub async fn request_response<Input: ToElements, Output: Debug + FromElement, Error>(
-    client: &Client,
+pub async fn request_response<Input: ToElements, Output: Debug + FromElement, Error: FromFault>(
+    transport: &dyn Transport,
     base_url: &str,
+    options: &Options,
     ns: &str,
     method: &str,
     input: &Input,
 ) -> Result<Result<Output, Error>, crate::Error> {
-    let mut v = input.to_elements();
-    let mut m = Method::new(method);
-
-    for el in v.drain(..) {
-        m = m.with(el);
-    }
-    let s = m.as_xml(ns);
-    trace!("sending: {}", s);
-
-    let response: String = client
-        .post(base_url)
-        .header("Content-Type", "text/xml")
-        .header("MessageType", "Call")
-        .body(s)
-        .send()
-        .await?
-        .text()
-        .await?;
-
-    trace!("received: {}", response);
-    let r = Response::from_xml(&response).unwrap();
+    let reply = request_response_with(
+        transport,
+        base_url,
+        options,
+        ns,
+        method,
+        input,
+        &Request::default(),
+    )
+    .await?;
+    Ok(reply.map(|reply| reply.output))
+}
+
+/// Like `request_response`, sending header elements and attachments with the body, and
+/// returning the ones received.
+pub async fn request_response_with<
+    Input: ToElements,
+    Output: Debug + FromElement,
+    Error: FromFault,
+>(
+    transport: &dyn Transport,
+    base_url: &str,
+    options: &Options,
+    ns: &str,
+    method: &str,
+    input: &Input,
+    request: &Request,
+) -> Result<Result<Reply<Output>, Error>, crate::Error> {
+    let (content_type, body) = request_body(base_url, ns, method, input, options, request)?;
+
+    let response = send(transport, base_url, options, &content_type, body).await?;
+    if !is_success(response.status) {
+        return declared_fault(status_error(&response));
+    }
+
+    let verifier = options.security.as_ref().and_then(|s| s.verifier.as_ref());
+    let (r, attachments, parts) =
+        match parse_response(response.header("Content-Type"), &response.body, verifier) {
+            Ok(parsed) => parsed,
+            Err(e) => return declared_fault(e),
+        };
     trace!("parsed: {:#?}", r);
-    let o = Output::from_element(&r.body);
+    if let Some(on_response_headers) = options.on_response_headers.as_ref() {
+        on_response_headers(&r.headers);
+    }
+    let o = xop::with_parts(parts, || Output::from_element(&r.body));
     trace!("output: {:#?}", o);
 
-    o.map(Ok)
+    o.map(|output| {
+        Ok(Reply {
+            output,
+            relates_to: wsa::relates_to(&r.headers),
+            headers: r.headers,
+            attachments,
+        })
+    })
+}
+
+/// The operation's fault when the error is a SOAP fault whose detail holds one it
+/// declares, the error itself otherwise, or the decoding error of a declared fault.
+fn declared_fault<Output, Error: FromFault>(
+    error: crate::Error,
+) -> Result<Result<Output, Error>, crate::Error> {
+    match &error {
+        crate::Error::Rpc(crate::rpser::RpcError::Fault { fault_detail, .. }) => {
+            match Error::from_fault(fault_detail) {
+                Some(fault) => fault.map(Err),
+                None => Err(error),
+            }
+        }
+        _ => Err(error),
+    }
+}
+
+/// Post a request body with the client's authentication.
+///
+/// When the server rejects a token from a provider, it is refreshed and the request sent
+/// again, once. Credentials still rejected with 401 Unauthorized or 403 Forbidden are an
+/// `Error::Auth`.
+async fn send(
+    transport: &dyn Transport,
+    base_url: &str,
+    options: &Options,
+    content_type: &str,
+    mut body: Vec<u8>,
+) -> Result<HttpResponse, crate::Error> {
+    let mut retried = false;
+    loop {
+        let mut headers = vec![
+            ("Content-Type".to_string(), content_type.to_string()),
+            ("MessageType".to_string(), "Call".to_string()),
+        ];
+        if let Some(auth) = options.auth.as_ref() {
+            headers.push(auth.to_header().await?);
+        }
+        // the body is only kept when the request may be sent again
+        let retry_body = options
+            .auth
+            .as_ref()
+            .filter(|auth| !retried && auth.is_refreshable())
+            .map(|_| body.clone());
+        let response = transport
+            .send(HttpRequest {
+                url: base_url.to_string(),
+                headers,
+                body,
+            })
+            .await?;
+
+        match (options.auth.as_ref(), retry_body) {
+            (Some(auth), Some(retry_body)) if response.status == 401 && auth.invalidate() => {
+                body = retry_body;
+                retried = true;
+            }
+            _ if response.status == 401 || response.status == 403 => {
+                return Err(crate::Error::Auth(format!(
+                    "credentials rejected with HTTP status {}",
+                    response.status
+                )));
+            }
+            _ => return Ok(response),
+        }
+    }
+}
+
+fn is_success(status: u16) -> bool {
+    (200..300).contains(&status)
+}
+
+/// Error for an unsuccessful response: the SOAP fault it holds, or its HTTP status.
+fn status_error(response: &HttpResponse) -> crate::Error {
+    match parse_response(response.header("Content-Type"), &response.body, None) {
+        Err(fault @ crate::Error::Rpc(crate::rpser::RpcError::Fault { .. })) => fault,
+        _ => crate::Error::Http {
+            status: response.status,
+            body: String::from_utf8_lossy(&response.body).into_owned(),
+        },
+    }
+}
+
+/// Serialize a request, returning its content type and body.
+fn request_body<Input: ToElements>(
+    base_url: &str,
+    ns: &str,
+    method: &str,
+    input: &Input,
+    options: &Options,
+    request: &Request,
+) -> Result<(String, Vec<u8>), crate::Error> {
+    let attachments = &request.attachments;
+    let signer = options.security.as_ref().and_then(|s| s.signer.as_ref());
+    let envelope = || -> Result<String, crate::Error> {
+        let mut m = Method::new(method);
+        m.attributes = input.to_attributes();
+        let addressing = request
+            .action
+            .iter()
+            .flat_map(|action| wsa::Addressing::new(action, base_url).to_elements());
+        m.headers = options
+            .security
+            .iter()
+            .map(|security| security.to_element())
+            .chain(addressing)
+            .chain(options.headers.iter().cloned())
+            .chain(request.headers.iter().cloned())
+            .collect();
+
+        for el in input.to_elements() {
+            m = m.with(el);
+        }
+        let mut envelope = m.envelope(ns);
+        if let Some(signer) = signer {
+            signer.sign(&mut envelope)?;
+        }
+        let s = envelope.to_string();
+        trace!("sending: {}", s);
+        Ok(s)
+    };
+
+    // signatures must cover binary values, which are then kept in the envelope
+    let (root, optimized, root_type) = match options.mtom.as_ref().filter(|_| signer.is_none()) {
+        Some(mtom) => {
+            let (envelope, optimized) = xop::optimize(mtom.threshold, envelope);
+            let root = Part::new(
+                r#"application/xop+xml; charset=UTF-8; type="text/xml""#,
+                envelope?.into_bytes(),
+            );
+            (root, optimized, "application/xop+xml")
+        }
+        None if attachments.is_empty() => {
+            return Ok(("text/xml".to_string(), envelope()?.into_bytes()));
+        }
+        None => {
+            let root = Part::new("text/xml; charset=UTF-8", envelope()?.into_bytes());
+            (root, vec![], "text/xml")
+        }
+    };
+
+    let root = root
+        .with_header("Content-Transfer-Encoding", "8bit")
+        .with_header("Content-ID", &format!("<{}>", ROOT_CONTENT_ID));
+    let optimized = optimized.into_iter().map(|attachment| {
+        Part::new("application/octet-stream", attachment.content)
+            .with_header("Content-Transfer-Encoding", "binary")
+            .with_header("Content-ID", &format!("<{}>", attachment.content_id))
+    });
+    let attachments = attachments.iter().map(|attachment| {
+        Part::new(&attachment.content_type, attachment.content.clone())
+            .with_header("Content-Transfer-Encoding", "binary")
+            .with_header("Content-ID", &format!("<{}>", attachment.content_id))
+    });
+    let parts = std::iter::once(root)
+        .chain(optimized)
+        .chain(attachments)
+        .collect::<Vec<_>>();
+
+    let (boundary, body) = multipart::encode(&parts);
+    let content_type = format!(
+        r#"multipart/related; type="{}"; start="<{}>"; start-info="text/xml"; boundary="{}""#,
+        root_type, ROOT_CONTENT_ID, boundary
+    );
+    Ok((content_type, body))
+}
+
+/// Parse a response body, returning the MTOM attachments of multipart responses, to read
+/// with `xop::with_parts`, and the other ones.
+///
+/// With a verifier, the signature of the envelope is checked first.
+fn parse_response(
+    content_type: Option<&str>,
+    body: &[u8],
+    verifier: Option<&dsig::Verifier>,
+) -> Result<(Response, Vec<Attachment>, xop::Parts), crate::Error> {
+    let content_type = match content_type.filter(|c| multipart::is_multipart(c)) {
+        Some(content_type) => content_type,
+        None => {
+            let response = String::from_utf8_lossy(body);
+            trace!("received: {}", response);
+            if let Some(verifier) = verifier {
+                verifier.verify(body)?;
+            }
+            return Ok((
+                Response::from_xml(&response)?,
+                vec![],
+                xop::Parts::default(),
+            ));
+        }
+    };
+
+    let mut multipart = multipart::parse(content_type, body)?;
+    let root = multipart.root().ok_or(multipart::Error::InvalidRoot)?;
+    trace!("received: {}", String::from_utf8_lossy(&root.body));
+    if let Some(verifier) = verifier {
+        verifier.verify(&root.body)?;
+    }
+    let root_id = root.content_id().map(|id| id.to_string());
+
+    let envelope =
+        Element::parse(root.body.as_slice()).map_err(|_| multipart::Error::InvalidRoot)?;
+    let parts = xop::resolve(&envelope, &mut multipart)?;
+    let attachments = multipart
+        .parts
+        .into_iter()
+        .enumerate()
+        .filter(|(i, part)| match root_id.as_deref() {
+            Some(root_id) => part.content_id() != Some(root_id),
+            None => *i != 0,
+        })
+        .filter(|(_, part)| !parts.content_ids().any(|id| part.content_id() == Some(id)))
+        .map(|(_, mut part)| Attachment {
+            content_id: part.content_id().unwrap_or_default().to_string(),
+            content_type: part
+                .header("Content-Type")
+                .unwrap_or("application/octet-stream")
+                .to_string(),
+            content: std::mem::take(&mut part.body),
+        })
+        .collect();
+
+    Ok((Response::from_envelope(envelope)?, attachments, parts))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::{BoxFuture, TokenProvider};
+    use crate::rpser::xml::to_base64;
+    use crate::rpser::RpcError;
+    use crate::transport::MockTransport;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+    use tokio::task::JoinHandle;
+
+    struct Upload(Vec<u8>);
+
+    impl ToElements for Upload {
+        fn to_elements(&self) -> Vec<Element> {
+            vec![xop::binary_element(Element::node("content"), &self.0)]
+        }
+    }
+
+    #[test]
+    fn sends_plain_envelope_without_mtom() {
+        let (content_type, body) = request_body(
+            "http://localhost/",
+            "urn:test",
+            "Upload",
+            &Upload(vec![1; 8]),
+            &Options::default(),
+            &Request::default(),
+        )
+        .unwrap();
+        assert_eq!(content_type, "text/xml");
+        let body = String::from_utf8(body).unwrap();
+        assert!(body.contains(&format!("<content>{}</content>", to_base64(&[1; 8]))));
+    }
+
+    #[test]
+    fn sends_large_values_as_attachments() {
+        let options = Options {
+            mtom: Some(Mtom { threshold: 8 }),
+            ..Options::default()
+        };
+        let (content_type, body) = request_body(
+            "http://localhost/",
+            "urn:test",
+            "Upload",
+            &Upload(vec![1; 8]),
+            &options,
+            &Request::default(),
+        )
+        .unwrap();
+        assert!(content_type.starts_with("multipart/related; type=\"application/xop+xml\""));
+
+        let multipart = multipart::parse(&content_type, &body).unwrap();
+        let root = multipart.root().unwrap();
+        assert!(root
+            .header("Content-Type")
+            .unwrap()
+            .starts_with("application/xop+xml"));
+        let envelope = String::from_utf8(root.body.clone()).unwrap();
+        assert!(envelope.contains(r#"href="cid:0.attachment@savon""#));
+        assert_eq!(multipart.part("0.attachment@savon").unwrap().body, [1; 8]);
+
+        // below the threshold, values stay in the envelope
+        let (content_type, body) = request_body(
+            "http://localhost/",
+            "urn:test",
+            "Upload",
+            &Upload(vec![1; 7]),
+            &options,
+            &Request::default(),
+        )
+        .unwrap();
+        let multipart = multipart::parse(&content_type, &body).unwrap();
+        assert_eq!(multipart.parts.len(), 1);
+        let envelope = String::from_utf8(multipart.parts[0].body.clone()).unwrap();
+        assert!(envelope.contains(&to_base64(&[1; 7])));
+    }
+
+    #[test]
+    fn sends_attachments_alongside_the_envelope() {
+        let scan = Attachment::new("scan", "image/png", vec![137, 80, 78, 71]);
+        assert!(scan.content_id.starts_with("scan="));
+        assert_eq!(scan.href(), format!("cid:{}", scan.content_id));
+
+        let request = Request {
+            attachments: vec![scan.clone()],
+            ..Request::default()
+        };
+        let (content_type, body) = request_body(
+            "http://localhost/",
+            "urn:test",
+            "Upload",
+            &Upload(vec![1; 8]),
+            &Options::default(),
+            &request,
+        )
+        .unwrap();
+        assert!(content_type.starts_with("multipart/related; type=\"text/xml\""));
+
+        let multipart = multipart::parse(&content_type, &body).unwrap();
+        let root = multipart.root().unwrap();
+        assert_eq!(root.header("Content-Type"), Some("text/xml; charset=UTF-8"));
+        let part = multipart.part(&scan.content_id).unwrap();
+        assert_eq!(part.header("Content-Type"), Some("image/png"));
+        assert_eq!(part.body, scan.content);
+    }
+
+    #[test]
+    fn sends_client_and_request_headers() {
+        let options = Options {
+            headers: vec![Element::node("Correlation").with_text("42")],
+            ..Options::default()
+        };
+        let request = Request {
+            headers: vec![Element::node("Session").with_text("s3cr3t")],
+            ..Request::default()
+        };
+        let (_, body) = request_body(
+            "http://localhost/",
+            "urn:test",
+            "Upload",
+            &Upload(vec![]),
+            &options,
+            &request,
+        )
+        .unwrap();
+        let body = String::from_utf8(body).unwrap();
+        assert!(body.contains(
+            "<soap:Header><Correlation>42</Correlation><Session>s3cr3t</Session></soap:Header>"
+        ));
+    }
+
+    #[test]
+    fn sends_security_header_first() {
+        let options = Options {
+            security: Some(wsse::Security::username_token(
+                "alice",
+                "secret",
+                wsse::PasswordType::Text,
+            )),
+            headers: vec![Element::node("Correlation").with_text("42")],
+            ..Options::default()
+        };
+        let (_, body) = request_body(
+            "http://localhost/",
+            "urn:test",
+            "Upload",
+            &Upload(vec![]),
+            &options,
+            &Request::default(),
+        )
+        .unwrap();
+
+        let envelope = Element::parse(body.as_slice()).unwrap();
+        let headers = envelope
+            .find_child("Header", Some(crate::rpser::SOAP_ENVELOPE_NS))
+            .unwrap()
+            .children
+            .iter()
+            .filter_map(|c| c.as_element())
+            .collect::<Vec<_>>();
+        assert!(headers[0].matches("Security", Some(wsse::WSSE_NS)));
+        assert_eq!(headers[0].attributes["mustUnderstand"], "1");
+        assert!(headers[1].matches("Correlation", None));
+        assert!(String::from_utf8(body)
+            .unwrap()
+            .contains(r#"soap:mustUnderstand="1""#));
+    }
+
+    #[test]
+    fn sends_addressing_headers() {
+        let options = Options {
+            headers: vec![Element::node("Correlation").with_text("42")],
+            ..Options::default()
+        };
+        let request = Request {
+            action: Some("urn:test/Upload".to_string()),
+            ..Request::default()
+        };
+        let (_, body) = request_body(
+            "http://localhost/upload",
+            "urn:test",
+            "Upload",
+            &Upload(vec![]),
+            &options,
+            &request,
+        )
+        .unwrap();
+
+        let envelope = Element::parse(body.as_slice()).unwrap();
+        let headers = envelope
+            .find_child("Header", Some(crate::rpser::SOAP_ENVELOPE_NS))
+            .unwrap()
+            .children
+            .iter()
+            .filter_map(|c| c.as_element())
+            .collect::<Vec<_>>();
+        let names = headers.iter().map(|h| h.name.as_str()).collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            ["Action", "MessageID", "ReplyTo", "To", "Correlation"]
+        );
+        assert_eq!(headers[0].get_text().unwrap(), "urn:test/Upload");
+        assert_eq!(headers[3].get_text().unwrap(), "http://localhost/upload");
+    }
+
+    #[test]
+    fn signs_requests_and_verifies_responses() {
+        let certificate = include_str!("../assets/test-cert.pem");
+        let security = wsse::Security::default()
+            .with_signer(
+                dsig::Signer::from_pem(include_str!("../assets/test-key.pem"), certificate)
+                    .unwrap(),
+            )
+            .with_verifier(dsig::Verifier::from_pem(certificate).unwrap());
+        let options = Options {
+            mtom: Some(Mtom { threshold: 8 }),
+            security: Some(security),
+            ..Options::default()
+        };
+        let (content_type, body) = request_body(
+            "http://localhost/",
+            "urn:test",
+            "Upload",
+            &Upload(vec![1; 8]),
+            &options,
+            &Request::default(),
+        )
+        .unwrap();
+        // binary values stay in the signed envelope
+        assert_eq!(content_type, "text/xml");
+        assert!(String::from_utf8_lossy(&body).contains(&to_base64(&[1; 8])));
+
+        // a server signing with the same key, as the request is echoed back
+        let verifier = options.security.as_ref().and_then(|s| s.verifier.as_ref());
+        assert!(parse_response(Some("text/xml"), &body, verifier).is_ok());
+        let tampered = String::from_utf8_lossy(&body).replace(&to_base64(&[1; 8]), "AAAA");
+        assert!(matches!(
+            parse_response(Some("text/xml"), tampered.as_bytes(), verifier),
+            Err(crate::Error::Dsig(dsig::Error::DigestMismatch { .. }))
+        ));
+    }
+
+    #[test]
+    fn resolves_mtom_responses() {
+        let envelope = format!(
+            r#"<soap:Envelope xmlns:soap="http://schemas.xmlsoap.org/soap/envelope/"><soap:Body><DownloadResponse><content><xop:Include xmlns:xop="{}" href="cid:pdf"/></content></DownloadResponse></soap:Body></soap:Envelope>"#,
+            xop::XOP_NS
+        );
+        let (boundary, body) = multipart::encode(&[
+            Part::new("application/xop+xml", envelope.into_bytes())
+                .with_header("Content-ID", "<env>"),
+            Part::new("application/pdf", b"%PDF".to_vec()).with_header("Content-ID", "<pdf>"),
+            Part::new("image/png", b"PNG".to_vec()).with_header("Content-ID", "<scan=1@host>"),
+        ]);
+        let content_type = format!(
+            "multipart/related; type=\"application/xop+xml\"; boundary=\"{}\"; start=\"<env>\"",
+            boundary
+        );
+
+        let (response, attachments, parts) =
+            parse_response(Some(&content_type), &body, None).unwrap();
+        let content = response.body.get_at_path(&["content"]).unwrap();
+        assert_eq!(
+            xop::with_parts(parts, || xop::binary_value(&content)).unwrap(),
+            b"%PDF"
+        );
+        assert_eq!(
+            attachments,
+            [Attachment {
+                content_id: "scan=1@host".to_string(),
+                content_type: "image/png".to_string(),
+                content: b"PNG".to_vec(),
+            }]
+        );
+
+        // generated types read the attachment, and nothing else holds it
+        #[derive(Debug)]
+        struct Download(Vec<u8>);
+
+        impl FromElement for Download {
+            fn from_element(element: &Element) -> Result<Self, crate::Error> {
+                xop::binary_value(&element.get_at_path(&["content"])?).map(Download)
+            }
+        }
+
+        let transport = MockTransport::new();
+        transport.push_response(HttpResponse {
+            status: 200,
+            headers: vec![("Content-Type".to_string(), content_type.clone())],
+            body: body.clone(),
+        });
+        let download: Result<Download, ()> = crate::blocking::block_on(request_response(
+            &transport,
+            "http://localhost/",
+            &Options::default(),
+            "urn:test",
+            "Download",
+            &Upload(vec![]),
+        ))
+        .unwrap();
+        assert_eq!(download.unwrap().0, b"%PDF");
+
+        let plain = r#"<soap:Envelope xmlns:soap="http://schemas.xmlsoap.org/soap/envelope/"><soap:Body><DownloadResponse/></soap:Body></soap:Envelope>"#;
+        let (response, attachments, _) =
+            parse_response(Some("text/xml; charset=utf-8"), plain.as_bytes(), None).unwrap();
+        assert_eq!(response.body.name, "DownloadResponse");
+        assert!(attachments.is_empty());
+    }
+
+    #[tokio::test]
+    async fn sends_through_any_transport() {
+        #[derive(Debug)]
+        struct Price(String);
+
+        impl FromElement for Price {
+            fn from_element(element: &Element) -> Result<Self, crate::Error> {
+                Ok(Price(
+                    element
+                        .get_at_path(&["price"])?
+                        .get_text()
+                        .unwrap_or_default()
+                        .to_string(),
+                ))
+            }
+        }
+
+        let transport = MockTransport::new();
+        transport.push_response(HttpResponse::xml(
+            r#"<soap:Envelope xmlns:soap="http://schemas.xmlsoap.org/soap/envelope/"><soap:Body><UploadResponse><price>42</price></UploadResponse></soap:Body></soap:Envelope>"#,
+        ));
+        let options = Options {
+            auth: Some(Auth::bearer("t0k3n")),
+            ..Options::default()
+        };
+        let price: Result<Price, ()> = request_response(
+            &transport,
+            "http://localhost/upload",
+            &options,
+            "urn:test",
+            "Upload",
+            &Upload(vec![]),
+        )
+        .await
+        .unwrap();
+        assert_eq!(price.unwrap().0, "42");
+
+        let requests = transport.requests();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].url, "http://localhost/upload");
+        assert_eq!(requests[0].header("content-type"), Some("text/xml"));
+        assert_eq!(requests[0].header("Authorization"), Some("Bearer t0k3n"));
+        assert!(String::from_utf8_lossy(&requests[0].body).contains("Upload"));
+
+        // nothing left to answer with
+        assert!(matches!(
+            one_way(
+                &transport,
+                "http://localhost/upload",
+                &options,
+                "urn:test",
+                "Upload",
+                &Upload(vec![]),
+            )
+            .await,
+            Err(crate::Error::Transport(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn decodes_declared_faults() {
+        #[derive(Debug, PartialEq)]
+        struct OutOfStock(String);
+
+        impl FromFault for OutOfStock {
+            fn from_fault(detail: &Element) -> Option<Result<Self, crate::Error>> {
+                let out_of_stock = detail.get_child("OutOfStock")?;
+                Some(
+                    out_of_stock
+                        .get_qualified("item", None)
+                        .map(|item| OutOfStock(item.get_text().unwrap_or_default().to_string()))
+                        .map_err(crate::Error::from),
+                )
+            }
+        }
+
+        #[derive(Debug)]
+        struct Price;
+
+        impl FromElement for Price {
+            fn from_element(_element: &Element) -> Result<Self, crate::Error> {
+                Ok(Price)
+            }
+        }
+
+        let transport = MockTransport::new();
+        let fault = |detail: &str| {
+            HttpResponse {
+            status: 500,
+            headers: vec![("Content-Type".to_string(), "text/xml".to_string())],
+            body: format!(
+                r#"<soap:Envelope xmlns:soap="http://schemas.xmlsoap.org/soap/envelope/"><soap:Body><soap:Fault><faultcode>soap:Server</faultcode><faultstring>failed</faultstring><detail>{}</detail></soap:Fault></soap:Body></soap:Envelope>"#,
+                detail
+            )
+            .into_bytes(),
+        }
+        };
+        transport.push_response(fault("<OutOfStock><item>pen</item></OutOfStock>"));
+        transport.push_response(fault("<Unknown/>"));
+        transport.push_response(fault("<OutOfStock/>"));
+
+        let options = Options::default();
+        let input = Upload(vec![]);
+        let call = || {
+            request_response::<_, Price, OutOfStock>(
+                &transport,
+                "http://localhost/",
+                &options,
+                "urn:test",
+                "Upload",
+                &input,
+            )
+        };
+        assert_eq!(
+            call().await.unwrap().unwrap_err(),
+            OutOfStock("pen".to_string())
+        );
+        // other faults are still errors
+        assert!(matches!(
+            call().await,
+            Err(crate::Error::Rpc(RpcError::Fault { fault_string, .. })) if fault_string == "failed"
+        ));
+        // declared faults which can't be decoded too
+        assert!(matches!(call().await, Err(crate::Error::Rpser(_))));
+    }
+
+    #[test]
+    fn reports_invalid_responses() {
+        let fault = r#"<soap:Envelope xmlns:soap="http://schemas.xmlsoap.org/soap/envelope/"><soap:Body><soap:Fault><faultcode>soap:Server</faultcode><faultstring>down</faultstring><detail/></soap:Fault></soap:Body></soap:Envelope>"#;
+        assert!(matches!(
+            parse_response(Some("text/xml"), fault.as_bytes(), None),
+            Err(crate::Error::Rpc(RpcError::Fault { fault_string, .. })) if fault_string == "down"
+        ));
+        for body in ["<html><body>Internal Server Error</body></html>", ""] {
+            assert!(matches!(
+                parse_response(Some("text/html"), body.as_bytes(), None),
+                Err(crate::Error::Rpc(_))
+            ));
+        }
+    }
+
+    /// Answer one request per status, returning their `Authorization` headers.
+    async fn serve(statuses: Vec<u16>) -> (String, JoinHandle<Vec<String>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = format!("http://{}/", listener.local_addr().unwrap());
+        let server = tokio::spawn(async move {
+            let mut authorizations = vec![];
+            for status in statuses {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let mut request = vec![];
+                let mut buffer = [0; 4096];
+                let head_end = loop {
+                    let read = stream.read(&mut buffer).await.unwrap();
+                    request.extend_from_slice(&buffer[..read]);
+                    if let Some(end) = request.windows(4).position(|w| w == b"\r\n\r\n") {
+                        break end + 4;
+                    }
+                };
+                let head = String::from_utf8_lossy(&request[..head_end]).to_lowercase();
+                let header = |name: &str| {
+                    head.lines()
+                        .find_map(|l| l.strip_prefix(name))
+                        .map(|v| v.trim().to_string())
+                };
+                let length = header("content-length:").map_or(0, |l| l.parse().unwrap());
+                while request.len() < head_end + length {
+                    let read = stream.read(&mut buffer).await.unwrap();
+                    request.extend_from_slice(&buffer[..read]);
+                }
+                authorizations.push(header("authorization:").unwrap_or_default());
+
+                let response = format!(
+                    "HTTP/1.1 {} Status\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                    status
+                );
+                stream.write_all(response.as_bytes()).await.unwrap();
+            }
+            authorizations
+        });
+        (base_url, server)
+    }
+
+    #[tokio::test]
+    async fn refreshes_rejected_tokens() {
+        struct Provider(AtomicUsize);
+
+        impl TokenProvider for Provider {
+            fn token(&self) -> BoxFuture<'_, Result<String, crate::Error>> {
+                Box::pin(async move { Ok(format!("token{}", self.0.load(Ordering::SeqCst))) })
+            }
+
+            fn invalidate(&self) {
+                self.0.fetch_add(1, Ordering::SeqCst);
+            }
+        }
+
+        let (base_url, server) = serve(vec![401, 200]).await;
+        let options = Options {
+            auth: Some(Auth::token(Provider(AtomicUsize::new(0)))),
+            ..Options::default()
+        };
+        let client = reqwest::Client::new();
+        one_way(
+            &client,
+            &base_url,
+            &options,
+            "urn:test",
+            "Upload",
+            &Upload(vec![]),
+        )
+        .await
+        .unwrap();
+        // header values are lower cased by the test server
+        assert_eq!(server.await.unwrap(), ["bearer token0", "bearer token1"]);
+
+        // the same body is sent again
+        let transport = MockTransport::new();
+        transport.push_response(HttpResponse {
+            status: 401,
+            headers: vec![],
+            body: vec![],
+        });
+        transport.push_response(HttpResponse {
+            status: 200,
+            headers: vec![],
+            body: vec![],
+        });
+        one_way(
+            &transport,
+            &base_url,
+            &options,
+            "urn:test",
+            "Upload",
+            &Upload(vec![]),
+        )
+        .await
+        .unwrap();
+        let requests = transport.requests();
+        assert_eq!(requests.len(), 2);
+        assert!(!requests[0].body.is_empty());
+        assert_eq!(requests[0].body, requests[1].body);
+        assert_eq!(requests[1].header("Authorization"), Some("Bearer token2"));
+
+        // fixed credentials are not retried
+        let (base_url, server) = serve(vec![401]).await;
+        let options = Options {
+            auth: Some(Auth::basic("alice", "secret")),
+            ..Options::default()
+        };
+        let result = one_way(
+            &client,
+            &base_url,
+            &options,
+            "urn:test",
+            "Upload",
+            &Upload(vec![]),
+        )
+        .await;
+        assert!(matches!(result, Err(crate::Error::Auth(_))));
+        assert_eq!(server.await.unwrap(), ["basic ywxpy2u6c2vjcmv0"]);
+
+        // refreshed tokens are retried once
+        let (base_url, server) = serve(vec![401, 401]).await;
+        let options = Options {
+            auth: Some(Auth::token(Provider(AtomicUsize::new(0)))),
+            ..Options::default()
+        };
+        let result = one_way(
+            &client,
+            &base_url,
+            &options,
+            "urn:test",
+            "Upload",
+            &Upload(vec![]),
+        )
+        .await;
+        assert!(matches!(result, Err(crate::Error::Auth(_))));
+        assert_eq!(server.await.unwrap(), ["bearer token0", "bearer token1"]);
+    }
+
+    #[tokio::test]
+    async fn reports_unsuccessful_statuses() {
+        let transport = MockTransport::new();
+        let fault = r#"<soap:Envelope xmlns:soap="http://schemas.xmlsoap.org/soap/envelope/"><soap:Body><soap:Fault><faultcode>soap:Server</faultcode><faultstring>down</faultstring><detail/></soap:Fault></soap:Body></soap:Envelope>"#;
+        transport.push_response(HttpResponse {
+            status: 500,
+            ..HttpResponse::xml(fault)
+        });
+        transport.push_response(HttpResponse {
+            status: 502,
+            headers: vec![("Content-Type".to_string(), "text/html".to_string())],
+            body: b"<html>Bad Gateway</html>".to_vec(),
+        });
+        transport.push_response(HttpResponse {
+            status: 403,
+            ..HttpResponse::default()
+        });
+
+        let (options, upload) = (Options::default(), Upload(vec![]));
+        let send = || {
+            one_way(
+                &transport,
+                "http://localhost/upload",
+                &options,
+                "urn:test",
+                "Upload",
+                &upload,
+            )
+        };
+        assert!(matches!(
+            send().await,
+            Err(crate::Error::Rpc(RpcError::Fault { fault_string, .. })) if fault_string == "down"
+        ));
+        assert!(matches!(
+            send().await,
+            Err(crate::Error::Http { status: 502, body }) if body.contains("Bad Gateway")
+        ));
+        assert!(matches!(send().await, Err(crate::Error::Auth(_))));
+    }
 }
@@ -1,9 +1,5 @@
 #[macro_use]
 extern crate log;
-extern crate proc_macro;
-extern crate proc_macro2;
-#[macro_use]
-extern crate quote;
 
 // reexport dependencies so they can be used from the generated code
 // without requiring more imports
@@ -11,17 +7,29 @@ extern crate quote;
 pub mod internal {
     pub use chrono;
     pub use reqwest;
+    pub use rust_decimal;
     pub use xmltree;
 }
 
+pub mod auth;
+pub mod blocking;
+pub mod c14n;
+pub mod dsig;
 mod error;
 pub mod gen;
 pub mod http;
 pub mod literal;
+pub mod multipart;
 pub mod rpser;
-pub mod wsdl;
+pub mod transport;
+pub mod wsa;
+pub mod wsse;
 
 pub use error::*;
+pub use savon_gen::wsdl;
+/// Generates the client for a WSDL file in place, with the `macros` feature.
+#[cfg(feature = "macros")]
+pub use savon_macros::wsdl;
 
 // Initialize logger for tests so we can set RUST_LOG for debugging
 #[cfg(test)]
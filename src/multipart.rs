@@ -0,0 +1,289 @@
+//! MIME `multipart/related` bodies, as used by MTOM.
+
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Error {
+    /// Content type has no boundary parameter.
+    MissingBoundary,
+    /// Body doesn't contain the boundary delimiters.
+    MissingDelimiter,
+    /// Part header line isn't `name: value`.
+    InvalidHeader { line: String },
+    /// Root part, holding the envelope, was not found or isn't XML.
+    InvalidRoot,
+    /// XOP include refers to a part which was not sent.
+    MissingPart { content_id: String },
+}
+
+/// Body part of a multipart message.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Part {
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl Part {
+    /// Create a part with its content type.
+    pub fn new(content_type: &str, body: Vec<u8>) -> Part {
+        Part {
+            headers: vec![("Content-Type".to_string(), content_type.to_string())],
+            body,
+        }
+    }
+
+    /// Add header to part.
+    pub fn with_header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// Value of a header, looked up case insensitively.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Content id, without its angle brackets.
+    pub fn content_id(&self) -> Option<&str> {
+        self.header("Content-ID").map(strip_angle_brackets)
+    }
+}
+
+/// Parsed multipart body.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Multipart {
+    /// Content id of the root part, from the `start` parameter.
+    pub start: Option<String>,
+    pub parts: Vec<Part>,
+}
+
+impl Multipart {
+    /// Root part: the one named by `start`, or the first one.
+    pub fn root(&self) -> Option<&Part> {
+        match self.start.as_deref() {
+            Some(start) => self.part(start),
+            None => self.parts.first(),
+        }
+    }
+
+    /// Part with a content id.
+    pub fn part(&self, content_id: &str) -> Option<&Part> {
+        self.parts
+            .iter()
+            .find(|p| p.content_id() == Some(content_id))
+    }
+}
+
+/// Split a content type into its lower case media type and its parameters.
+pub fn parse_content_type(content_type: &str) -> (String, Vec<(String, String)>) {
+    let mut fields = split_unquoted(content_type, ';').into_iter();
+    let media_type = fields
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase();
+    let parameters = fields
+        .filter_map(|field| {
+            let (name, value) = field.split_once('=')?;
+            let value = value.trim();
+            let value = value
+                .strip_prefix('"')
+                .and_then(|v| v.strip_suffix('"'))
+                .unwrap_or(value);
+            Some((name.trim().to_ascii_lowercase(), value.to_string()))
+        })
+        .collect();
+    (media_type, parameters)
+}
+
+/// Whether a content type is `multipart/related`.
+pub fn is_multipart(content_type: &str) -> bool {
+    parse_content_type(content_type).0 == "multipart/related"
+}
+
+/// Parse a `multipart/related` body.
+pub fn parse(content_type: &str, body: &[u8]) -> Result<Multipart, Error> {
+    let (_, parameters) = parse_content_type(content_type);
+    let parameter = |name: &str| {
+        parameters
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.clone())
+    };
+    let boundary = parameter("boundary").ok_or(Error::MissingBoundary)?;
+    let delimiter = format!("\r\n--{}", boundary);
+    let delimiter = delimiter.as_bytes();
+
+    // the first delimiter may start the body, without a line break
+    let mut rest = match find(body, &delimiter[2..]) {
+        Some(0) => &body[delimiter.len() - 2..],
+        _ => {
+            let start = find(body, delimiter).ok_or(Error::MissingDelimiter)?;
+            &body[start + delimiter.len()..]
+        }
+    };
+
+    let mut parts = vec![];
+    while !rest.starts_with(b"--") {
+        // skip transport padding after the delimiter
+        let line_end = find(rest, b"\r\n").ok_or(Error::MissingDelimiter)?;
+        rest = &rest[line_end + 2..];
+        let end = find(rest, delimiter).ok_or(Error::MissingDelimiter)?;
+        parts.push(parse_part(&rest[..end])?);
+        rest = &rest[end + delimiter.len()..];
+    }
+
+    Ok(Multipart {
+        start: parameter("start").map(|s| strip_angle_brackets(&s).to_string()),
+        parts,
+    })
+}
+
+/// Encode parts as a multipart body, returning it with the boundary used.
+pub fn encode(parts: &[Part]) -> (String, Vec<u8>) {
+    let boundary = format!("uuid:{}", unique_id());
+
+    let mut body = Vec::with_capacity(parts.iter().map(|p| p.body.len() + 256).sum());
+    for part in parts {
+        body.extend_from_slice(format!("--{}\r\n", boundary).as_bytes());
+        for (name, value) in part.headers.iter() {
+            body.extend_from_slice(format!("{}: {}\r\n", name, value).as_bytes());
+        }
+        body.extend_from_slice(b"\r\n");
+        body.extend_from_slice(&part.body);
+        body.extend_from_slice(b"\r\n");
+    }
+    body.extend_from_slice(format!("--{}--\r\n", boundary).as_bytes());
+
+    (boundary, body)
+}
+
+/// Random identifier, for boundaries and content ids.
+pub(crate) fn unique_id() -> String {
+    format!("savon-{:016x}", RandomState::new().build_hasher().finish())
+}
+
+fn parse_part(part: &[u8]) -> Result<Part, Error> {
+    let (headers, body) = if part.starts_with(b"\r\n") {
+        (&part[..0], &part[2..])
+    } else {
+        match find(part, b"\r\n\r\n") {
+            Some(end) => (&part[..end], &part[end + 4..]),
+            None => (part, &part[part.len()..]),
+        }
+    };
+
+    let headers = String::from_utf8_lossy(headers)
+        .split("\r\n")
+        .filter(|line| !line.is_empty())
+        .map(|line| match line.split_once(':') {
+            Some((name, value)) => Ok((name.trim().to_string(), value.trim().to_string())),
+            None => Err(Error::InvalidHeader {
+                line: line.to_string(),
+            }),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(Part {
+        headers,
+        body: body.to_vec(),
+    })
+}
+
+fn split_unquoted(s: &str, separator: char) -> Vec<&str> {
+    let mut fields = vec![];
+    let mut quoted = false;
+    let mut start = 0;
+    for (i, c) in s.char_indices() {
+        match c {
+            '"' => quoted = !quoted,
+            c if c == separator && !quoted => {
+                fields.push(&s[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    fields.push(&s[start..]);
+    fields
+}
+
+fn strip_angle_brackets(s: &str) -> &str {
+    let s = s.trim();
+    s.strip_prefix('<')
+        .and_then(|s| s.strip_suffix('>'))
+        .unwrap_or(s)
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|w| w == needle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_content_type() {
+        let (media_type, parameters) = parse_content_type(
+            r#"Multipart/Related; type="application/xop+xml"; boundary="uuid:a;b"; start="<root>""#,
+        );
+        assert_eq!(media_type, "multipart/related");
+        assert_eq!(
+            parameters,
+            [
+                ("type".to_string(), "application/xop+xml".to_string()),
+                ("boundary".to_string(), "uuid:a;b".to_string()),
+                ("start".to_string(), "<root>".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn encodes_and_parses_parts() {
+        let parts = vec![
+            Part::new("text/xml", b"<Envelope/>".to_vec()).with_header("Content-ID", "<root>"),
+            Part::new("application/octet-stream", vec![0, 13, 10, 45, 45, 255])
+                .with_header("Content-ID", "<0@savon>"),
+        ];
+        let (boundary, body) = encode(&parts);
+        let content_type = format!(
+            r#"multipart/related; boundary="{}"; start="<root>""#,
+            boundary
+        );
+
+        let multipart = parse(&content_type, &body).unwrap();
+        assert_eq!(multipart.parts, parts);
+        assert_eq!(multipart.root().unwrap().body, b"<Envelope/>");
+        assert_eq!(
+            multipart.part("0@savon").unwrap().body,
+            [0, 13, 10, 45, 45, 255]
+        );
+    }
+
+    #[test]
+    fn parses_preamble_and_padding() {
+        let body =
+            b"preamble\r\n--b \r\ncontent-id: <a>\r\n\r\nfirst\r\n--b\r\n\r\nsecond\r\n--b--\r\n";
+        let multipart = parse("multipart/related; boundary=b", body).unwrap();
+        assert_eq!(multipart.root().unwrap().body, b"first");
+        assert_eq!(
+            multipart.part("a").unwrap().header("Content-ID"),
+            Some("<a>")
+        );
+        assert_eq!(multipart.parts[1].body, b"second");
+
+        assert_eq!(
+            parse("multipart/related", body),
+            Err(Error::MissingBoundary)
+        );
+        assert_eq!(
+            parse("multipart/related; boundary=c", body),
+            Err(Error::MissingDelimiter)
+        );
+    }
+}
@@ -1,6 +1,7 @@
 //! Remote procedule call implementation and serialization to XML.
 
 pub mod xml;
+pub mod xop;
 
 use std::fmt;
 use std::result;
@@ -8,11 +9,20 @@ use std::result;
 use self::xml::BuildElement;
 use xmltree::Element;
 
+/// SOAP 1.1 envelope namespace.
+pub const SOAP_ENVELOPE_NS: &str = "http://schemas.xmlsoap.org/soap/envelope/";
+/// SOAP 1.2 envelope namespace.
+pub const SOAP12_ENVELOPE_NS: &str = "http://www.w3.org/2003/05/soap-envelope";
+
 /// XML method representation.
 #[derive(Debug)]
 pub struct Method {
     pub name: String,
     pub args: Vec<Element>,
+    /// Attributes of the method element, as `(namespace, name, value)`.
+    pub attributes: Vec<(Option<String>, String, String)>,
+    /// Children of the envelope's `Header`.
+    pub headers: Vec<Element>,
 }
 
 impl Method {
@@ -21,9 +31,24 @@ impl Method {
         Method {
             name: name.into(),
             args: vec![],
+            attributes: vec![],
+            headers: vec![],
         }
     }
 
+    /// Add header element to the envelope.
+    pub fn with_header(mut self, header: Element) -> Self {
+        self.headers.push(header);
+        self
+    }
+
+    /// Add attribute to method element.
+    pub fn with_attr(mut self, namespace: Option<String>, name: &str, value: &str) -> Self {
+        self.attributes
+            .push((namespace, name.to_string(), value.to_string()));
+        self
+    }
+
     /// Add argument to method.
     ///
     /// The `arg` is XML Element.
@@ -33,21 +58,93 @@ impl Method {
     }
 
     /// Convert method to full XML envelope.
+    ///
+    /// Arguments in a namespace get a prefix declared on the envelope.
     pub fn as_xml(&self, api_url: &str) -> String {
+        self.envelope(api_url).to_string()
+    }
+
+    /// Build the envelope element, with every namespace declared on it.
+    pub fn envelope(&self, api_url: &str) -> Element {
         let namespace = "ns";
 
-        let envelope = Element::node("soap:Envelope")
-            .with_attr("xmlns:soap", "http://schemas.xmlsoap.org/soap/envelope/")
-            .with_attr(format!("xmlns:{}", namespace), api_url)
-            .with_children(vec![
-                Element::node("soap:Header"),
-                Element::node("soap:Body").with_child(
-                    Element::node(format!("{}:{}", namespace, self.name))
-                        .with_children_from_iter(self.args.iter()),
-                ),
-            ]);
+        let mut prefixes = vec![(api_url.to_string(), namespace.to_string())];
+        let mut method = Element::node(format!("{}:{}", namespace, self.name))
+            .with_attributes(self.attributes.iter().cloned());
+        assign_prefixes(&mut method, &mut prefixes);
+        let mut prefixed = |elements: &[Element]| {
+            elements
+                .iter()
+                .map(|e| {
+                    let mut e = e.cloned();
+                    assign_prefixes(&mut e, &mut prefixes);
+                    e
+                })
+                .collect::<Vec<_>>()
+        };
+        let headers = prefixed(&self.headers);
+        let args = prefixed(&self.args);
+
+        let soap = |name: &str| {
+            let mut element = Element::node(name).with_namespace(SOAP_ENVELOPE_NS);
+            element.prefix = Some("soap".to_string());
+            element
+        };
+        let envelope = prefixes.iter().fold(
+            soap("Envelope").with_attr("xmlns:soap", SOAP_ENVELOPE_NS),
+            |envelope, (uri, prefix)| envelope.with_attr(format!("xmlns:{}", prefix), uri),
+        );
+        envelope.with_children(vec![
+            soap("Header").with_children(headers),
+            soap("Body").with_child(method.with_children(args)),
+        ])
+    }
+}
+
+/// Give a prefix to every namespaced element and attribute lacking one, recording the
+/// `(namespace, prefix)` declarations needed.
+fn assign_prefixes(element: &mut Element, prefixes: &mut Vec<(String, String)>) {
+    fn prefix_for(uri: &str, prefixes: &mut Vec<(String, String)>) -> String {
+        // the envelope namespace is always declared, for `soap:mustUnderstand` and the like
+        if uri == SOAP_ENVELOPE_NS {
+            return "soap".to_string();
+        }
+        match prefixes.iter().find(|(u, _)| u == uri) {
+            Some((_, prefix)) => prefix.clone(),
+            None => {
+                let prefix = format!("ns{}", prefixes.len());
+                prefixes.push((uri.to_string(), prefix.clone()));
+                prefix
+            }
+        }
+    }
 
-        envelope.to_string()
+    if let (Some(uri), None) = (element.namespace.as_ref(), element.prefix.as_ref()) {
+        element.prefix = Some(prefix_for(uri, prefixes));
+    }
+
+    // qualified attributes are keyed in Clark notation, `{namespace}name`
+    let qualified = element
+        .attributes
+        .keys()
+        .filter(|k| k.starts_with('{'))
+        .cloned()
+        .collect::<Vec<_>>();
+    for key in qualified {
+        if let (Some(end), Some(value)) = (key.find('}'), element.attributes.remove(&key)) {
+            let prefix = prefix_for(&key[1..end], prefixes);
+            element
+                .attributes
+                .insert(format!("{}:{}", prefix, &key[end + 1..]), value);
+        }
+    }
+
+    for child in element
+        .children
+        .iter_mut()
+        .filter_map(|c| c.as_mut_element())
+    {
+        assign_prefixes(child, prefixes);
     }
 }
 
@@ -55,37 +152,91 @@ impl Method {
 #[derive(Debug)]
 pub struct Response {
     pub body: Element,
+    /// Children of the envelope's `Header`.
+    pub headers: Vec<Element>,
 }
 
 impl Response {
     /// Parse response from XML.
+    ///
+    /// Envelope elements are matched on their namespace, whatever prefix the server uses.
     pub fn from_xml(xml: &str) -> Result<Response> {
         let mut bytes = xml.as_bytes();
-        let mut element = Element::parse(&mut bytes).unwrap();
+        let envelope = Element::parse(&mut bytes).map_err(|e| RpcError::InvalidXml {
+            reason: e.to_string(),
+        })?;
+        Response::from_envelope(envelope)
+    }
 
-        if element.name != "Envelope" {
-            return Err(RpcError::UnexpectedElement { tag: element.name });
-        }
-        element = element.descend(&["Body"])?;
+    /// Extract response from a parsed envelope.
+    pub fn from_envelope(mut element: Element) -> Result<Response> {
+        let namespace = match element.namespace.as_deref() {
+            Some(ns @ SOAP_ENVELOPE_NS) | Some(ns @ SOAP12_ENVELOPE_NS)
+                if element.name == "Envelope" =>
+            {
+                ns.to_string()
+            }
+            _ => return Err(RpcError::UnexpectedElement { tag: element.name }),
+        };
+        let headers = element
+            .find_child("Header", Some(&namespace))
+            .map(|header| {
+                header
+                    .children
+                    .iter()
+                    .filter_map(|c| c.as_element())
+                    .cloned()
+                    .collect()
+            })
+            .unwrap_or_default();
+        element = element.descend_qualified("Body", Some(&namespace))?;
         element = element.descend_first()?;
 
-        if element.name == "Fault" {
-            return Err(RpcError::Fault {
-                fault_code: element
-                    .get_at_path(&["faultcode"])?
-                    .get_text()
+        if element.matches("Fault", Some(&namespace)) {
+            let text = |element: Option<&Element>| {
+                element
+                    .and_then(|e| e.get_text())
                     .map(|t| t.to_string())
-                    .unwrap_or_default(),
-                fault_string: element
-                    .get_at_path(&["faultstring"])?
-                    .get_text()
-                    .map(|t| t.to_string())
-                    .unwrap_or_default(),
-                fault_detail: Box::new(element.get_at_path(&["detail"])?),
+                    .unwrap_or_default()
+            };
+            // SOAP 1.2 fault children are qualified, and the code and reason nested
+            let (fault_code, fault_string, fault_detail) = if namespace == SOAP12_ENVELOPE_NS {
+                let ns = Some(namespace.as_str());
+                (
+                    text(
+                        element
+                            .find_child("Code", ns)
+                            .and_then(|c| c.find_child("Value", ns)),
+                    ),
+                    text(
+                        element
+                            .find_child("Reason", ns)
+                            .and_then(|r| r.find_child("Text", ns)),
+                    ),
+                    element.find_child("Detail", ns),
+                )
+            } else {
+                (
+                    text(Some(&element.get_at_path(&["faultcode"])?)),
+                    text(Some(&element.get_at_path(&["faultstring"])?)),
+                    element.get_child("detail"),
+                )
+            };
+            return Err(RpcError::Fault {
+                fault_code,
+                fault_string,
+                fault_detail: Box::new(
+                    fault_detail
+                        .cloned()
+                        .unwrap_or_else(|| Element::new("detail")),
+                ),
             });
         }
 
-        Ok(Response { body: element })
+        Ok(Response {
+            body: element,
+            headers,
+        })
     }
 }
 
@@ -99,13 +250,20 @@ impl fmt::Display for Method {
 #[derive(Debug, PartialEq, Eq)]
 pub enum RpcError {
     Fault {
+        /// `faultcode`, or `Code/Value` in SOAP 1.2.
         fault_code: String,
+        /// `faultstring`, or the first `Reason/Text` in SOAP 1.2.
         fault_string: String,
+        /// `detail`, or `Detail` in SOAP 1.2, empty when the fault has none.
         fault_detail: Box<Element>,
     },
     XmlError {
         error: self::xml::Error,
     },
+    /// Response is not well formed XML, like an HTML error page or an empty body.
+    InvalidXml {
+        reason: String,
+    },
     ExpectedElementText {
         tag: String,
     },
@@ -165,6 +323,128 @@ mod test {
         };
     }
 
+    #[test]
+    fn can_deal_with_soap12_fault() {
+        let faulty_response = r#"<?xml version="1.0" encoding="utf-8"?>
+            <env:Envelope xmlns:env="http://www.w3.org/2003/05/soap-envelope">
+                <env:Body>
+                    <env:Fault>
+                        <env:Code>
+                            <env:Value>env:Sender</env:Value>
+                            <env:Subcode><env:Value>m:MessageTimeout</env:Value></env:Subcode>
+                        </env:Code>
+                        <env:Reason>
+                            <env:Text xml:lang="en">Sender Timeout</env:Text>
+                            <env:Text xml:lang="fr">Délai dépassé</env:Text>
+                        </env:Reason>
+                        <env:Detail>
+                            <m:MaxTime xmlns:m="urn:timeouts">P5M</m:MaxTime>
+                        </env:Detail>
+                    </env:Fault>
+                </env:Body>
+            </env:Envelope>
+        "#;
+
+        match Response::from_xml(faulty_response) {
+            Err(RpcError::Fault {
+                fault_code,
+                fault_string,
+                fault_detail,
+            }) => {
+                assert_eq!(fault_code, "env:Sender");
+                assert_eq!(fault_string, "Sender Timeout");
+                assert!(fault_detail
+                    .find_child("MaxTime", Some("urn:timeouts"))
+                    .is_some());
+            }
+            other => panic!("expected a fault, received {:?}", other),
+        };
+
+        // the detail is optional
+        let without_detail = r#"<soap:Envelope xmlns:soap="http://schemas.xmlsoap.org/soap/envelope/"><soap:Body><soap:Fault><faultcode>soap:Server</faultcode><faultstring>down</faultstring></soap:Fault></soap:Body></soap:Envelope>"#;
+        assert!(matches!(
+            Response::from_xml(without_detail),
+            Err(RpcError::Fault { fault_detail, .. }) if fault_detail.children.is_empty()
+        ));
+    }
+
+    #[test]
+    fn matches_envelope_on_namespace() {
+        let default_ns_response = r#"<?xml version="1.0" encoding="utf-8"?>
+            <Envelope xmlns="http://schemas.xmlsoap.org/soap/envelope/">
+                <Header/>
+                <Body>
+                    <loginResponse xmlns="urn:confluence">
+                        <loginReturn>a3a8ecc6d5</loginReturn>
+                    </loginResponse>
+                </Body>
+            </Envelope>
+        "#;
+
+        let response = Response::from_xml(default_ns_response).unwrap();
+        assert!(response.headers.is_empty());
+        assert!(response
+            .body
+            .matches("loginResponse", Some("urn:confluence")));
+        assert!(response
+            .body
+            .find_child("loginReturn", Some("urn:confluence"))
+            .is_some());
+        assert!(response.body.find_child("loginReturn", None).is_none());
+
+        let foreign_envelope = r#"<?xml version="1.0" encoding="utf-8"?>
+            <soap:Envelope xmlns:soap="urn:not-soap">
+                <soap:Body><loginResponse/></soap:Body>
+            </soap:Envelope>
+        "#;
+
+        assert_eq!(
+            Response::from_xml(foreign_envelope).unwrap_err(),
+            RpcError::UnexpectedElement {
+                tag: "Envelope".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn declares_argument_namespaces() {
+        let xml = Method::new("GetPrice")
+            .with(
+                Element::node("ticker")
+                    .with_namespace("http://example.com/stock.xsd")
+                    .with_text("SOAP"),
+            )
+            .with(
+                Element::node("currency")
+                    .with_namespace("http://example.com/stock.wsdl")
+                    .with_text("EUR"),
+            )
+            .with(
+                Element::node("unqualified")
+                    .with_qualified_attr("http://example.com/stock.xsd", "unit", "EUR")
+                    .with_text("1"),
+            )
+            .as_xml("http://example.com/stock.wsdl");
+
+        assert!(xml.contains(r#"xmlns:ns="http://example.com/stock.wsdl""#));
+        assert!(xml.contains(r#"xmlns:ns1="http://example.com/stock.xsd""#));
+        assert!(xml.contains("<ns:GetPrice><ns1:ticker>SOAP</ns1:ticker><ns:currency>EUR</ns:currency><unqualified ns1:unit=\"EUR\">1</unqualified></ns:GetPrice>"));
+
+        let parsed = Element::parse(xml.as_bytes()).unwrap();
+        let method = parsed
+            .get_child("Body")
+            .and_then(|b| b.get_child("GetPrice"))
+            .unwrap();
+        let ticker = method
+            .get_qualified("ticker", Some("http://example.com/stock.xsd"))
+            .unwrap();
+        assert_eq!(ticker.get_text().unwrap(), "SOAP");
+        assert!(method
+            .get_qualified("ticker", Some("http://example.com/stock.wsdl"))
+            .is_err());
+        assert!(method.get_qualified("unqualified", None).is_ok());
+    }
+
     #[test]
     fn returns_result_element() {
         let ok_response = r#"<?xml version="1.0" encoding="utf-8"?>
@@ -190,4 +470,30 @@ mod test {
             ),
         };
     }
+
+    #[test]
+    fn sends_and_reads_headers() {
+        let xml = Method::new("GetPrice")
+            .with_header(
+                Element::node("Session")
+                    .with_namespace("urn:session")
+                    .with_text("s3cr3t"),
+            )
+            .as_xml("http://example.com/stock.wsdl");
+        assert!(xml.contains(r#"xmlns:ns1="urn:session""#));
+        assert!(xml.contains("<soap:Header><ns1:Session>s3cr3t</ns1:Session></soap:Header>"));
+
+        let response = r#"<?xml version="1.0" encoding="utf-8"?>
+            <soap:Envelope xmlns:soap="http://schemas.xmlsoap.org/soap/envelope/">
+                <soap:Header>
+                    <Session xmlns="urn:session">renewed</Session>
+                </soap:Header>
+                <soap:Body><GetPriceResponse/></soap:Body>
+            </soap:Envelope>
+        "#;
+        let response = Response::from_xml(response).unwrap();
+        assert_eq!(response.headers.len(), 1);
+        assert!(response.headers[0].matches("Session", Some("urn:session")));
+        assert_eq!(response.headers[0].get_text().unwrap(), "renewed");
+    }
 }
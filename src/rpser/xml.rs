@@ -1,11 +1,20 @@
 //! Helper trait to deal with XML Element tree.
 
+use base64::engine::general_purpose::STANDARD;
+use base64::read::DecoderReader;
+use base64::Engine;
 use chrono::offset::Utc;
-use chrono::{DateTime, ParseError};
+use chrono::{DateTime, FixedOffset, NaiveDate, NaiveDateTime, NaiveTime, ParseError};
+use rust_decimal::Decimal;
 use std::collections::HashMap;
-use std::num::ParseIntError;
+use std::fmt;
+use std::io::{self, Read};
+use std::num::{ParseFloatError, ParseIntError};
 use xmltree::Element;
 
+/// XML Schema instance namespace, for `xsi:nil` and `xsi:type`.
+pub const XSI_NS: &str = "http://www.w3.org/2001/XMLSchema-instance";
+
 #[derive(Debug, PartialEq, Eq)]
 pub enum Error {
     /// Element was expected at path, but was not found.
@@ -21,11 +30,552 @@ pub enum Error {
     /// Can't parse received element.
     ParseIntError { name: String, inner: ParseIntError },
     /// Can't parse received element.
+    ParseFloatError {
+        name: String,
+        inner: ParseFloatError,
+    },
+    /// Can't parse received element.
     ParseDateTimeError { name: String, inner: ParseError },
+    /// Can't parse received element.
+    ParseBooleanError { name: String, given: String },
+    /// Can't parse received element.
+    ParseDurationError { name: String, given: String },
+    /// Can't parse received element.
+    ParseDecimalError { name: String, given: String },
+    /// Can't decode received binary element.
+    ParseBinaryError { name: String, reason: String },
+    /// Attribute was expected, but was not found.
+    AttributeNotFound { name: String },
+    /// Element or attribute doesn't have the value fixed by the schema.
+    UnexpectedFixedValue {
+        name: String,
+        expected: String,
+        given: String,
+    },
+    /// Expected another element.
+    UnexpectedElement {
+        name: String,
+        namespace: Option<String>,
+        expected: String,
+    },
+    /// Element was found too few or too many times.
+    UnexpectedOccurences {
+        name: String,
+        found: usize,
+        min: usize,
+        max: Option<usize>,
+    },
     /// empty
     Empty,
 }
 
+/// Parse a value from its XML Schema lexical representation.
+///
+/// `name` is the element or attribute the text comes from, for error reporting.
+pub trait FromXmlText: Sized {
+    fn from_xml_text(name: &str, text: &str) -> Result<Self, Error>;
+}
+
+impl FromXmlText for String {
+    fn from_xml_text(_name: &str, text: &str) -> Result<Self, Error> {
+        Ok(text.to_string())
+    }
+}
+
+impl FromXmlText for bool {
+    fn from_xml_text(name: &str, text: &str) -> Result<Self, Error> {
+        match text.trim() {
+            "true" | "1" => Ok(true),
+            "false" | "0" => Ok(false),
+            other => Err(Error::ParseBooleanError {
+                name: name.to_string(),
+                given: other.to_string(),
+            }),
+        }
+    }
+}
+
+impl FromXmlText for i64 {
+    fn from_xml_text(name: &str, text: &str) -> Result<Self, Error> {
+        text.trim().parse().map_err(|e| Error::ParseIntError {
+            name: name.to_string(),
+            inner: e,
+        })
+    }
+}
+
+impl FromXmlText for f64 {
+    fn from_xml_text(name: &str, text: &str) -> Result<Self, Error> {
+        match text.trim() {
+            "INF" => Ok(f64::INFINITY),
+            "-INF" => Ok(f64::NEG_INFINITY),
+            t => t.parse().map_err(|e| Error::ParseFloatError {
+                name: name.to_string(),
+                inner: e,
+            }),
+        }
+    }
+}
+
+/// Parses an `xs:decimal` exactly, keeping its scale (`1.50` stays `1.50`). The exponent
+/// form of `xs:float` and `xs:double` is accepted as well.
+///
+/// Values which `Decimal` cannot hold exactly, with more than 28 fractional digits or
+/// beyond about ±7.9e28, are rejected rather than rounded.
+impl FromXmlText for Decimal {
+    fn from_xml_text(name: &str, text: &str) -> Result<Self, Error> {
+        let text = text.trim();
+        let unsigned = text.strip_prefix('+').unwrap_or(text);
+        let plain = match unsigned.contains(['e', 'E']) {
+            true => without_exponent(unsigned),
+            false => Some(unsigned.to_string()),
+        };
+        plain
+            .and_then(|plain| Decimal::from_str_exact(&plain).ok())
+            .ok_or_else(|| Error::ParseDecimalError {
+                name: name.to_string(),
+                given: text.to_string(),
+            })
+    }
+}
+
+/// Writes a number in exponent form, like `1.5E2`, as a plain decimal, `150`.
+fn without_exponent(text: &str) -> Option<String> {
+    let (mantissa, exponent) = text.split_once(['e', 'E'])?;
+    let exponent = exponent.strip_prefix('+').unwrap_or(exponent);
+    let exponent: i32 = exponent.parse().ok()?;
+    let (negative, mantissa) = match mantissa.strip_prefix('-') {
+        Some(mantissa) => (true, mantissa),
+        None => (false, mantissa),
+    };
+    let (integer, fraction) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+    let digits = format!("{}{}", integer, fraction);
+    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    // way past what a Decimal holds
+    if exponent.abs() > 64 {
+        return None;
+    }
+
+    let point = integer.len() as i32 + exponent;
+    let plain = if point <= 0 {
+        format!("0.{}{}", "0".repeat(-point as usize), digits)
+    } else if point as usize >= digits.len() {
+        format!("{}{}", digits, "0".repeat(point as usize - digits.len()))
+    } else {
+        format!(
+            "{}.{}",
+            &digits[..point as usize],
+            &digits[point as usize..]
+        )
+    };
+    Some(if negative {
+        format!("-{}", plain)
+    } else {
+        plain
+    })
+}
+
+/// Parses an `xs:dateTime`, with any number of fractional second digits. Values without
+/// a timezone are taken as UTC.
+fn parse_datetime(name: &str, text: &str) -> Result<DateTime<FixedOffset>, Error> {
+    let text = text.trim();
+    DateTime::parse_from_rfc3339(text)
+        .or_else(|_| {
+            NaiveDateTime::parse_from_str(text, "%Y-%m-%dT%H:%M:%S%.f")
+                .map(|dt| dt.and_utc().fixed_offset())
+        })
+        .map_err(|e| Error::ParseDateTimeError {
+            name: name.to_string(),
+            inner: e,
+        })
+}
+
+/// Removes the optional timezone of an `xs:date` or `xs:time`.
+fn strip_timezone(text: &str) -> &str {
+    let text = text.trim();
+    if let Some(text) = text.strip_suffix('Z') {
+        return text;
+    }
+    match text.len().checked_sub(6).and_then(|i| text.get(i..)) {
+        Some(tz) if tz.starts_with(['+', '-']) && tz.as_bytes()[3] == b':' => {
+            &text[..text.len() - 6]
+        }
+        _ => text,
+    }
+}
+
+impl FromXmlText for DateTime<FixedOffset> {
+    fn from_xml_text(name: &str, text: &str) -> Result<Self, Error> {
+        parse_datetime(name, text)
+    }
+}
+
+impl FromXmlText for DateTime<Utc> {
+    fn from_xml_text(name: &str, text: &str) -> Result<Self, Error> {
+        parse_datetime(name, text).map(|dt| dt.with_timezone(&Utc))
+    }
+}
+
+/// The timezone, if any, is ignored.
+impl FromXmlText for NaiveDate {
+    fn from_xml_text(name: &str, text: &str) -> Result<Self, Error> {
+        NaiveDate::parse_from_str(strip_timezone(text), "%Y-%m-%d").map_err(|e| {
+            Error::ParseDateTimeError {
+                name: name.to_string(),
+                inner: e,
+            }
+        })
+    }
+}
+
+/// The timezone, if any, is ignored.
+impl FromXmlText for NaiveTime {
+    fn from_xml_text(name: &str, text: &str) -> Result<Self, Error> {
+        NaiveTime::parse_from_str(strip_timezone(text), "%H:%M:%S%.f").map_err(|e| {
+            Error::ParseDateTimeError {
+                name: name.to_string(),
+                inner: e,
+            }
+        })
+    }
+}
+
+impl FromXmlText for Duration {
+    fn from_xml_text(name: &str, text: &str) -> Result<Self, Error> {
+        Duration::parse(text.trim()).ok_or_else(|| Error::ParseDurationError {
+            name: name.to_string(),
+            given: text.to_string(),
+        })
+    }
+}
+
+/// Write a value in its XML Schema lexical representation.
+pub trait ToXmlText {
+    fn to_xml_text(&self) -> String;
+}
+
+impl ToXmlText for String {
+    fn to_xml_text(&self) -> String {
+        self.clone()
+    }
+}
+
+impl ToXmlText for bool {
+    fn to_xml_text(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl ToXmlText for i64 {
+    fn to_xml_text(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl ToXmlText for f64 {
+    fn to_xml_text(&self) -> String {
+        if self.is_nan() {
+            "NaN".to_string()
+        } else if self.is_infinite() {
+            if *self > 0.0 { "INF" } else { "-INF" }.to_string()
+        } else {
+            self.to_string()
+        }
+    }
+}
+
+impl ToXmlText for Decimal {
+    fn to_xml_text(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl ToXmlText for DateTime<FixedOffset> {
+    fn to_xml_text(&self) -> String {
+        self.to_rfc3339_opts(chrono::SecondsFormat::AutoSi, true)
+    }
+}
+
+impl ToXmlText for DateTime<Utc> {
+    fn to_xml_text(&self) -> String {
+        self.to_rfc3339_opts(chrono::SecondsFormat::AutoSi, true)
+    }
+}
+
+impl ToXmlText for NaiveDate {
+    fn to_xml_text(&self) -> String {
+        self.format("%Y-%m-%d").to_string()
+    }
+}
+
+impl ToXmlText for NaiveTime {
+    fn to_xml_text(&self) -> String {
+        self.format("%H:%M:%S%.f").to_string()
+    }
+}
+
+impl ToXmlText for Duration {
+    fn to_xml_text(&self) -> String {
+        self.to_string()
+    }
+}
+
+/// `xs:duration` value. Years and months don't have a fixed length, so they are kept
+/// apart from days and time.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Duration {
+    pub negative: bool,
+    pub years: u32,
+    pub months: u32,
+    pub days: u32,
+    pub hours: u32,
+    pub minutes: u32,
+    pub seconds: u32,
+    /// Fraction of the seconds, kept exact down to the nanosecond.
+    pub nanoseconds: u32,
+}
+
+impl Duration {
+    /// Parses the `PnYnMnDTnHnMnS` form, like `P1Y2M` or `-PT1.5S`.
+    fn parse(text: &str) -> Option<Duration> {
+        let (negative, text) = match text.strip_prefix('-') {
+            Some(text) => (true, text),
+            None => (false, text),
+        };
+        let text = text.strip_prefix('P')?;
+        let (date, time) = match text.find('T') {
+            Some(index) if index + 1 < text.len() => (&text[..index], Some(&text[index + 1..])),
+            Some(_) => return None,
+            None => (text, None),
+        };
+        if date.is_empty() && time.is_none() {
+            return None;
+        }
+
+        // components in order, each designator at most once
+        fn components<'a>(text: &'a str, designators: &[char]) -> Option<Vec<(char, &'a str)>> {
+            let mut result = Vec::new();
+            let mut rest = text;
+            let mut allowed = designators;
+            while !rest.is_empty() {
+                let index = rest.find(|c: char| c.is_ascii_alphabetic())?;
+                let designator = rest[index..].chars().next()?;
+                let position = allowed.iter().position(|d| *d == designator)?;
+                if index == 0 {
+                    return None;
+                }
+                result.push((designator, &rest[..index]));
+                allowed = &allowed[position + 1..];
+                rest = &rest[index + 1..];
+            }
+            Some(result)
+        }
+
+        let mut duration = Duration {
+            negative,
+            ..Default::default()
+        };
+        for (designator, value) in components(date, &['Y', 'M', 'D'])? {
+            let value = value.parse().ok()?;
+            match designator {
+                'Y' => duration.years = value,
+                'M' => duration.months = value,
+                _ => duration.days = value,
+            }
+        }
+        for (designator, value) in components(time.unwrap_or_default(), &['H', 'M', 'S'])? {
+            match designator {
+                'H' => duration.hours = value.parse().ok()?,
+                'M' => duration.minutes = value.parse().ok()?,
+                _ => {
+                    let (seconds, fraction) = match value.split_once('.') {
+                        Some((seconds, fraction)) => (seconds, Some(fraction)),
+                        None => (value, None),
+                    };
+                    if !seconds.starts_with(|c: char| c.is_ascii_digit()) {
+                        return None;
+                    }
+                    duration.seconds = seconds.parse().ok()?;
+                    if let Some(fraction) = fraction {
+                        duration.nanoseconds = parse_nanoseconds(fraction)?;
+                    }
+                }
+            }
+        }
+
+        Some(duration)
+    }
+
+    /// Length of the duration, if it has no years or months.
+    pub fn to_chrono(&self) -> Option<chrono::Duration> {
+        if self.years != 0 || self.months != 0 {
+            return None;
+        }
+        let seconds = ((self.days as i64 * 24 + self.hours as i64) * 60 + self.minutes as i64) * 60
+            + self.seconds as i64;
+        let duration = chrono::Duration::seconds(seconds)
+            + chrono::Duration::nanoseconds(self.nanoseconds as i64);
+        Some(if self.negative { -duration } else { duration })
+    }
+}
+
+/// Nanoseconds of the digits after the decimal point, which must not be more precise.
+fn parse_nanoseconds(fraction: &str) -> Option<u32> {
+    if fraction.is_empty() || !fraction.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let (digits, rest) = fraction.split_at(fraction.len().min(9));
+    if rest.bytes().any(|b| b != b'0') {
+        return None;
+    }
+    format!("{:0<9}", digits).parse().ok()
+}
+
+impl fmt::Display for Duration {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.negative {
+            write!(f, "-")?;
+        }
+        write!(f, "P")?;
+        for (value, designator) in [(self.years, 'Y'), (self.months, 'M'), (self.days, 'D')] {
+            if value != 0 {
+                write!(f, "{}{}", value, designator)?;
+            }
+        }
+        let date_empty = self.years == 0 && self.months == 0 && self.days == 0;
+        let time_empty =
+            self.hours == 0 && self.minutes == 0 && self.seconds == 0 && self.nanoseconds == 0;
+        if !time_empty || date_empty {
+            write!(f, "T")?;
+            if self.hours != 0 {
+                write!(f, "{}H", self.hours)?;
+            }
+            if self.minutes != 0 {
+                write!(f, "{}M", self.minutes)?;
+            }
+            if self.seconds != 0 || self.nanoseconds != 0 || (self.hours == 0 && self.minutes == 0)
+            {
+                write!(f, "{}", self.seconds)?;
+                if self.nanoseconds != 0 {
+                    let fraction = format!("{:09}", self.nanoseconds);
+                    write!(f, ".{}", fraction.trim_end_matches('0'))?;
+                }
+                write!(f, "S")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Reader over the bytes of a string, skipping the whitespace allowed in `xs:base64Binary`.
+struct SkipWhitespace<'a>(&'a [u8]);
+
+impl Read for SkipWhitespace<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let mut n = 0;
+        while n < buf.len() {
+            match self.0.split_first() {
+                Some((b, rest)) => {
+                    self.0 = rest;
+                    if !b.is_ascii_whitespace() {
+                        buf[n] = *b;
+                        n += 1;
+                    }
+                }
+                None => break,
+            }
+        }
+        Ok(n)
+    }
+}
+
+/// Decode an `xs:base64Binary` value.
+///
+/// The text is decoded as a stream, so large payloads are not copied before decoding.
+pub fn from_base64(name: &str, text: &str) -> Result<Vec<u8>, Error> {
+    let mut decoded = Vec::with_capacity(text.len() / 4 * 3);
+    DecoderReader::new(SkipWhitespace(text.as_bytes()), &STANDARD)
+        .read_to_end(&mut decoded)
+        .map_err(|e| Error::ParseBinaryError {
+            name: name.to_string(),
+            reason: e.to_string(),
+        })?;
+    Ok(decoded)
+}
+
+/// Encode an `xs:base64Binary` value.
+pub fn to_base64(value: &[u8]) -> String {
+    STANDARD.encode(value)
+}
+
+/// Decode an `xs:hexBinary` value.
+pub fn from_hex(name: &str, text: &str) -> Result<Vec<u8>, Error> {
+    let error = |reason: &str| Error::ParseBinaryError {
+        name: name.to_string(),
+        reason: reason.to_string(),
+    };
+    let digits = text.trim().as_bytes();
+    if digits.len() % 2 != 0 {
+        return Err(error("odd number of hex digits"));
+    }
+
+    let digit = |b: u8| {
+        (b as char)
+            .to_digit(16)
+            .ok_or_else(|| error("invalid hex digit"))
+    };
+    digits
+        .chunks(2)
+        .map(|pair| Ok((digit(pair[0])? << 4 | digit(pair[1])?) as u8))
+        .collect()
+}
+
+/// Encode an `xs:hexBinary` value, in the canonical upper case form.
+pub fn to_hex(value: &[u8]) -> String {
+    const DIGITS: &[u8; 16] = b"0123456789ABCDEF";
+    let mut text = String::with_capacity(value.len() * 2);
+    for b in value {
+        text.push(DIGITS[(b >> 4) as usize] as char);
+        text.push(DIGITS[(b & 0xf) as usize] as char);
+    }
+    text
+}
+
+/// Check that a value matches the one fixed by the schema.
+pub fn check_fixed(name: &str, text: &str, fixed: &str) -> Result<(), Error> {
+    if text.trim() == fixed {
+        Ok(())
+    } else {
+        Err(Error::UnexpectedFixedValue {
+            name: name.to_string(),
+            expected: fixed.to_string(),
+            given: text.to_string(),
+        })
+    }
+}
+
+/// Check that a repeated element was found between `min` and `max` (if bounded) times.
+pub fn check_occurences(
+    name: &str,
+    found: usize,
+    min: usize,
+    max: Option<usize>,
+) -> Result<(), Error> {
+    if found < min || max.is_some_and(|max| found > max) {
+        Err(Error::UnexpectedOccurences {
+            name: name.to_string(),
+            found,
+            min,
+            max,
+        })
+    } else {
+        Ok(())
+    }
+}
+
 /// Helper trait for building `xmltree::Element`.
 ///
 /// Such convenience methods were not available in `xmltree::Element`, so they are added
@@ -60,6 +610,10 @@ pub trait BuildElement {
         S: Into<String>;
     /// Modify node's name.
     fn with_name<S>(self, name: S) -> Self
+    where
+        S: Into<String>;
+    /// Modify node's namespace.
+    fn with_namespace<S>(self, namespace: S) -> Self
     where
         S: Into<String>;
     /// Modify node's text.
@@ -71,6 +625,20 @@ pub trait BuildElement {
     where
         KS: Into<String>,
         VS: Into<String>;
+    /// Add attributes, as `(namespace, name, value)`.
+    fn with_attributes<I>(self, attributes: I) -> Self
+    where
+        I: IntoIterator<Item = (Option<String>, String, String)>;
+    /// Add attribute in a namespace, given a prefix when serialized in an envelope.
+    ///
+    /// Parsed elements only keep the local name of attributes, so this is for building only.
+    fn with_qualified_attr<NS, KS, VS>(self, namespace: NS, key: KS, value: VS) -> Self
+    where
+        NS: Into<String>,
+        KS: Into<String>,
+        VS: Into<String>;
+    /// Mark as `xsi:nil="true"`, declaring the `xsi` prefix on the node itself.
+    fn with_nil(self) -> Self;
     /// Add child.
     fn with_child(self, child: Self) -> Self;
     /// Add children.
@@ -95,6 +663,26 @@ pub trait BuildElement {
     /// Get clone of child element at path.
     fn get_at_path(&self, path: &[&str]) -> Result<Element, Error>;
 
+    /// Whether the element is marked as `xsi:nil`.
+    fn is_nil(&self) -> bool;
+
+    /// Check the element's local name and namespace, regardless of its prefix.
+    ///
+    /// A `None` namespace matches unqualified elements only.
+    fn matches(&self, name: &str, namespace: Option<&str>) -> bool;
+
+    /// Find first child element by local name and namespace, regardless of its prefix.
+    fn find_child(&self, name: &str, namespace: Option<&str>) -> Option<&Element>;
+
+    /// Find all child elements by local name and namespace, regardless of their prefix.
+    fn find_children(&self, name: &str, namespace: Option<&str>) -> Vec<&Element>;
+
+    /// Descend into child element by local name and namespace, destroying the parent.
+    fn descend_qualified(self, name: &str, namespace: Option<&str>) -> Result<Element, Error>;
+
+    /// Get clone of child element by local name and namespace, regardless of its prefix.
+    fn get_qualified(&self, name: &str, namespace: Option<&str>) -> Result<Element, Error>;
+
     /// Extract the value of `long` type from the text.
     fn as_long(&self) -> Result<i64, Error>;
 
@@ -107,7 +695,7 @@ pub trait BuildElement {
     /// Extract the value of `string` type from the text.
     fn as_string(&self) -> Result<String, Error>;
 
-    /// Extract the value of `DateTime` type from the text.
+    /// Extract the value of `DateTime` type from the text, taken as UTC without timezone.
     fn as_datetime(&self) -> Result<DateTime<Utc>, Error>;
 }
 
@@ -147,6 +735,14 @@ impl BuildElement for Element {
         self
     }
 
+    fn with_namespace<S>(mut self, namespace: S) -> Self
+    where
+        S: Into<String>,
+    {
+        self.namespace = Some(namespace.into());
+        self
+    }
+
     fn with_text<S>(mut self, text: S) -> Self
     where
         S: Into<String>,
@@ -164,6 +760,33 @@ impl BuildElement for Element {
         self
     }
 
+    fn with_attributes<I>(self, attributes: I) -> Self
+    where
+        I: IntoIterator<Item = (Option<String>, String, String)>,
+    {
+        attributes
+            .into_iter()
+            .fold(self, |element, (namespace, key, value)| match namespace {
+                Some(namespace) => element.with_qualified_attr(namespace, key, value),
+                None => element.with_attr(key, value),
+            })
+    }
+
+    fn with_qualified_attr<NS, KS, VS>(self, namespace: NS, key: KS, value: VS) -> Self
+    where
+        NS: Into<String>,
+        KS: Into<String>,
+        VS: Into<String>,
+    {
+        // Clark notation, resolved into a prefix by `Method::as_xml`
+        self.with_attr(format!("{{{}}}{}", namespace.into(), key.into()), value)
+    }
+
+    fn with_nil(self) -> Self {
+        self.with_attr("xmlns:xsi", XSI_NS)
+            .with_attr("xsi:nil", "true")
+    }
+
     fn with_child(mut self, child: Self) -> Self {
         self.children.push(xmltree::XMLNode::Element(child));
         self
@@ -219,12 +842,15 @@ impl BuildElement for Element {
         }
     }
 
-    fn descend_first(mut self) -> Result<Element, Error> {
-        if self.children.is_empty() {
-            Err(Error::ExpectedNotEmpty { parent: self.name })
-        } else {
-            Ok(self.children.remove(0).as_element().unwrap().clone())
-        }
+    fn descend_first(self) -> Result<Element, Error> {
+        let parent = self.name;
+        self.children
+            .into_iter()
+            .find_map(|c| match c {
+                xmltree::XMLNode::Element(e) => Some(e),
+                _ => None,
+            })
+            .ok_or(Error::ExpectedNotEmpty { parent })
     }
 
     fn get_at_path(&self, path: &[&str]) -> Result<Element, Error> {
@@ -251,6 +877,57 @@ impl BuildElement for Element {
         }
     }
 
+    fn is_nil(&self) -> bool {
+        // parsed attributes only keep their local name, built ones keep their prefix or
+        // namespace
+        let nil = self
+            .attributes
+            .get("nil")
+            .or_else(|| self.attributes.get("xsi:nil"))
+            .or_else(|| self.attributes.get(&format!("{{{}}}nil", XSI_NS)));
+        matches!(nil.map(|s| s.as_str()), Some("true") | Some("1"))
+    }
+
+    fn matches(&self, name: &str, namespace: Option<&str>) -> bool {
+        self.name == name && self.namespace.as_deref().filter(|ns| !ns.is_empty()) == namespace
+    }
+
+    fn find_child(&self, name: &str, namespace: Option<&str>) -> Option<&Element> {
+        self.children
+            .iter()
+            .filter_map(|c| c.as_element())
+            .find(|child| child.matches(name, namespace))
+    }
+
+    fn find_children(&self, name: &str, namespace: Option<&str>) -> Vec<&Element> {
+        self.children
+            .iter()
+            .filter_map(|c| c.as_element())
+            .filter(|child| child.matches(name, namespace))
+            .collect()
+    }
+
+    fn descend_qualified(self, name: &str, namespace: Option<&str>) -> Result<Element, Error> {
+        self.children
+            .into_iter()
+            .filter_map(|c| match c {
+                xmltree::XMLNode::Element(e) => Some(e),
+                _ => None,
+            })
+            .find(|child| child.matches(name, namespace))
+            .ok_or_else(|| Error::NotFoundAtPath {
+                path: vec![name.into()],
+            })
+    }
+
+    fn get_qualified(&self, name: &str, namespace: Option<&str>) -> Result<Element, Error> {
+        self.find_child(name, namespace)
+            .map(|child| child.cloned())
+            .ok_or_else(|| Error::NotFoundAtPath {
+                path: vec![name.into()],
+            })
+    }
+
     fn as_int(&self) -> Result<i32, Error> {
         let text = get_typed_string(self, "int")?;
         Ok(match text.parse() {
@@ -283,15 +960,7 @@ impl BuildElement for Element {
 
     fn as_datetime(&self) -> Result<DateTime<Utc>, Error> {
         let text = get_typed_string(self, "dateTime")?;
-        Ok(match text.parse::<DateTime<Utc>>() {
-            Ok(ref value) => *value,
-            Err(e) => {
-                return Err(Error::ParseDateTimeError {
-                    name: self.name.clone(),
-                    inner: e,
-                });
-            }
-        })
+        DateTime::<Utc>::from_xml_text(&self.name, &text)
     }
 
     fn as_boolean(&self) -> Result<bool, Error> {
@@ -303,7 +972,9 @@ impl BuildElement for Element {
 fn get_typed_string(element: &Element, value_type: &str) -> Result<String, Error> {
     Ok(
         match (element.attributes.get("type"), &element.get_text()) {
-            (Some(value), &Some(ref text)) if value.ends_with(value_type) => text.to_string(),
+            (Some(value), Some(text)) if value.ends_with(value_type) => text.to_string(),
+            // document/literal messages usually don't carry xsi:type
+            (None, Some(text)) => text.to_string(),
             (other_type, _) => {
                 return Err(Error::ExpectedElementWithType {
                     name: element.name.clone(),
@@ -314,3 +985,265 @@ fn get_typed_string(element: &Element, value_type: &str) -> Result<String, Error
         },
     )
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_datetime_lexical_forms() {
+        let parse = |s| DateTime::<FixedOffset>::from_xml_text("d", s).unwrap();
+
+        let dt = parse("2020-03-04T05:06:07+02:00");
+        assert_eq!(dt.offset().local_minus_utc(), 7200);
+        assert_eq!(dt.to_xml_text(), "2020-03-04T05:06:07+02:00");
+        assert_eq!(
+            parse("2020-03-04T05:06:07Z").to_xml_text(),
+            "2020-03-04T05:06:07Z"
+        );
+        assert_eq!(
+            parse("2020-03-04T05:06:07").to_xml_text(),
+            "2020-03-04T05:06:07Z"
+        );
+        assert_eq!(
+            parse("2020-03-04T05:06:07.1234").to_xml_text(),
+            "2020-03-04T05:06:07.123400Z"
+        );
+        assert!(DateTime::<FixedOffset>::from_xml_text("d", "2020-03-04").is_err());
+
+        let element = Element::node("d").with_text(" 2020-03-04T05:06:07+02:00 ");
+        assert_eq!(
+            element.as_datetime().unwrap().to_xml_text(),
+            "2020-03-04T03:06:07Z"
+        );
+    }
+
+    #[test]
+    fn parse_dates_and_times() {
+        let date = NaiveDate::from_xml_text("d", "2020-03-04+02:00").unwrap();
+        assert_eq!(date.to_xml_text(), "2020-03-04");
+        assert_eq!(NaiveDate::from_xml_text("d", "2020-03-04Z").unwrap(), date);
+
+        let time = NaiveTime::from_xml_text("t", "05:06:07.5Z").unwrap();
+        assert_eq!(time.to_xml_text(), "05:06:07.500");
+        assert_eq!(
+            NaiveTime::from_xml_text("t", "05:06:07")
+                .unwrap()
+                .to_xml_text(),
+            "05:06:07"
+        );
+    }
+
+    #[test]
+    fn parse_durations() {
+        let parse = |s| Duration::from_xml_text("d", s);
+
+        let duration = parse("P1Y2M3DT4H5M6.5S").unwrap();
+        assert_eq!((duration.years, duration.months, duration.days), (1, 2, 3));
+        assert_eq!((duration.hours, duration.minutes), (4, 5));
+        assert_eq!((duration.seconds, duration.nanoseconds), (6, 500_000_000));
+        assert_eq!(duration.to_xml_text(), "P1Y2M3DT4H5M6.5S");
+        assert_eq!(duration.to_chrono(), None);
+
+        // exact fractions, so durations can be compared and hashed
+        let duration = parse("PT0.000000001S").unwrap();
+        assert_eq!(duration.to_chrono(), Some(chrono::Duration::nanoseconds(1)));
+        assert_eq!(duration.to_xml_text(), "PT0.000000001S");
+        assert_eq!(parse("PT1.50000000000S"), parse("PT1.5S"));
+        let durations = ["PT1.5S", "PT1.50S", "PT2S"]
+            .iter()
+            .map(|s| parse(s).unwrap())
+            .collect::<std::collections::HashSet<_>>();
+        assert_eq!(durations.len(), 2);
+
+        let duration = parse("-PT90S").unwrap();
+        assert_eq!(duration.to_chrono(), Some(chrono::Duration::seconds(-90)));
+        assert_eq!(duration.to_xml_text(), "-PT90S");
+        assert_eq!(Duration::default().to_xml_text(), "PT0S");
+        assert_eq!(parse("P1D").unwrap().to_xml_text(), "P1D");
+
+        for invalid in [
+            "",
+            "P",
+            "PT",
+            "1D",
+            "P1H",
+            "PT1D",
+            "P1M1Y",
+            "PT.5S",
+            "P-1D",
+            "PT1.S",
+            "PT1.0000000001S",
+        ] {
+            assert!(parse(invalid).is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn decimals_round_trip_exactly() {
+        for text in ["1.50", "-0.000001", "12345678901234567890.123456789", "0"] {
+            let value = Decimal::from_xml_text("d", text).unwrap();
+            assert_eq!(value.to_xml_text(), text);
+        }
+        assert_eq!(
+            Decimal::from_xml_text("d", " +2.5 ").unwrap().to_xml_text(),
+            "2.5"
+        );
+        assert_eq!(
+            Decimal::from_xml_text("d", "1.5E2").unwrap().to_xml_text(),
+            "150"
+        );
+        assert_eq!(
+            Decimal::from_xml_text("d", "-25e-3").unwrap().to_xml_text(),
+            "-0.025"
+        );
+
+        // beyond what a Decimal holds exactly
+        for text in [
+            "0.12345678901234567890123456789",
+            "123456789012345678901234567890",
+            "1.234567890123456789012345678901e1",
+            "1e-29",
+            "1.5e30",
+            "1e999999",
+        ] {
+            assert!(Decimal::from_xml_text("d", text).is_err(), "{}", text);
+        }
+        assert!(Decimal::from_xml_text("d", "INF").is_err());
+        assert!(Decimal::from_xml_text("d", "1,5").is_err());
+    }
+
+    #[test]
+    fn nil_elements() {
+        assert!(
+            Element::parse(format!(r#"<a xmlns:i="{}" i:nil="true"/>"#, XSI_NS).as_bytes())
+                .unwrap()
+                .is_nil()
+        );
+        assert!(Element::node("a").with_nil().is_nil());
+        assert!(Element::node("a")
+            .with_qualified_attr(XSI_NS, "nil", "1")
+            .is_nil());
+        assert!(!Element::node("a")
+            .with_qualified_attr(XSI_NS, "nil", "false")
+            .is_nil());
+        assert!(!Element::node("a").is_nil());
+    }
+
+    #[test]
+    fn write_special_floats() {
+        assert_eq!(f64::INFINITY.to_xml_text(), "INF");
+        assert_eq!(f64::NEG_INFINITY.to_xml_text(), "-INF");
+        assert_eq!(f64::NAN.to_xml_text(), "NaN");
+        assert_eq!(1.5.to_xml_text(), "1.5");
+    }
+
+    #[test]
+    fn binary_round_trip() {
+        let bytes = (0..=255).collect::<Vec<u8>>();
+        assert_eq!(from_base64("b", &to_base64(&bytes)).unwrap(), bytes);
+        assert_eq!(from_hex("h", &to_hex(&bytes)).unwrap(), bytes);
+
+        assert_eq!(to_base64(b"savon"), "c2F2b24=");
+        assert_eq!(from_base64("b", " c2F2\n b24=\r\n").unwrap(), b"savon");
+        assert_eq!(from_base64("b", "").unwrap(), b"");
+        assert!(from_base64("b", "c2F2b24").is_err());
+        assert!(from_base64("b", "c2F*b24=").is_err());
+
+        assert_eq!(to_hex(&[0x0f, 0xa0]), "0FA0");
+        assert_eq!(from_hex("h", " 0fA0 ").unwrap(), [0x0f, 0xa0]);
+        assert!(from_hex("h", "0FA").is_err());
+        assert!(from_hex("h", "0G").is_err());
+    }
+
+    #[test]
+    fn generator_checks_the_same_values() {
+        use savon_gen::{lexical::check, wsdl::SimpleType};
+
+        fn agree<T: FromXmlText>(simple_type: SimpleType, texts: &[&str]) {
+            for text in texts {
+                assert_eq!(
+                    check(&simple_type, text).is_ok(),
+                    T::from_xml_text("v", text).is_ok(),
+                    "{:?} {:?}",
+                    simple_type,
+                    text
+                );
+            }
+        }
+
+        agree::<bool>(SimpleType::Boolean, &["true", " 0 ", "yes", "TRUE", ""]);
+        agree::<i64>(
+            SimpleType::Int,
+            &["42", " -7 ", "+3", "1.5", "99999999999999999999"],
+        );
+        agree::<f64>(
+            SimpleType::Float,
+            &["1.5", "INF", "-INF", "NaN", "1e3", "1,5", ""],
+        );
+        agree::<Decimal>(
+            SimpleType::Decimal,
+            &[
+                "1.50", "+2.5", "1.5E2", "-25e-3", "1e-29", "1.5e30", "1e999999", "INF",
+            ],
+        );
+        agree::<DateTime<FixedOffset>>(
+            SimpleType::DateTime,
+            &[
+                "2020-03-04T05:06:07+02:00",
+                "2020-03-04T05:06:07.1234",
+                "2020-03-04",
+            ],
+        );
+        agree::<NaiveDate>(
+            SimpleType::Date,
+            &["2020-03-04+02:00", "2020-03-04Z", "04/03/2020"],
+        );
+        agree::<NaiveTime>(SimpleType::Time, &["05:06:07.5Z", "05:06:07", "5h"]);
+        agree::<Duration>(
+            SimpleType::Duration,
+            &[
+                "P1Y2M3DT4H5M6.5S",
+                "-PT90S",
+                "PT0.000000001S",
+                "PT1.50000000000S",
+                "P+1D",
+                "",
+                "P",
+                "PT",
+                "1D",
+                "P1H",
+                "PT1D",
+                "P1M1Y",
+                "PT.5S",
+                "P-1D",
+                "PT1.S",
+                "PT1.0000000001S",
+                "P99999999999D",
+            ],
+        );
+
+        let binary = |simple_type: SimpleType,
+                      from: fn(&str, &str) -> Result<Vec<u8>, Error>,
+                      texts: &[&str]| {
+            for text in texts {
+                assert_eq!(
+                    check(&simple_type, text).is_ok(),
+                    from("v", text).is_ok(),
+                    "{:?}",
+                    text
+                );
+            }
+        };
+        binary(
+            SimpleType::Base64Binary,
+            from_base64,
+            &["c2F2b24=", " c2F2\n b24=\r\n", "", "c2F2b24", "c2F*b24="],
+        );
+        binary(
+            SimpleType::HexBinary,
+            from_hex,
+            &["0FA0", " 0fA0 ", "0FA", "0G"],
+        );
+    }
+}
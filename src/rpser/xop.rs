@@ -0,0 +1,285 @@
+//! XML-binary Optimized Packaging, sending binary values as MTOM attachments.
+
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+
+use xmltree::Element;
+
+use super::xml::{from_base64, to_base64, BuildElement};
+use crate::multipart::{Error, Multipart};
+
+/// XOP namespace.
+pub const XOP_NS: &str = "http://www.w3.org/2004/08/xop/include";
+
+/// Binary value moved out of the envelope.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Attachment {
+    pub content_id: String,
+    pub content: Vec<u8>,
+}
+
+struct Optimizer {
+    threshold: usize,
+    attachments: Vec<Attachment>,
+}
+
+thread_local! {
+    static OPTIMIZER: RefCell<Option<Optimizer>> = const { RefCell::new(None) };
+}
+
+/// Restores the enclosing optimizer, even if serialization panics.
+struct Restore(Option<Optimizer>);
+
+impl Drop for Restore {
+    fn drop(&mut self) {
+        OPTIMIZER.with(|o| *o.borrow_mut() = self.0.take());
+    }
+}
+
+/// Run `serialize`, moving binary values of at least `threshold` bytes it writes with
+/// `binary_element` to attachments.
+pub fn optimize<T>(threshold: usize, serialize: impl FnOnce() -> T) -> (T, Vec<Attachment>) {
+    let optimizer = Optimizer {
+        threshold,
+        attachments: vec![],
+    };
+    let restore = Restore(OPTIMIZER.with(|o| o.borrow_mut().replace(optimizer)));
+    let value = serialize();
+    let attachments = OPTIMIZER
+        .with(|o| o.borrow_mut().take())
+        .map(|o| o.attachments)
+        .unwrap_or_default();
+    drop(restore);
+    (value, attachments)
+}
+
+/// Set an `xs:base64Binary` value as the content of an element.
+///
+/// Within `optimize`, large values are replaced by an `xop:Include` of an attachment.
+/// Otherwise they are written as base64 text.
+pub fn binary_element(element: Element, value: &[u8]) -> Element {
+    let content_id = OPTIMIZER.with(|o| match o.borrow_mut().as_mut() {
+        Some(optimizer) if value.len() >= optimizer.threshold => {
+            let content_id = format!("{}.attachment@savon", optimizer.attachments.len());
+            optimizer.attachments.push(Attachment {
+                content_id: content_id.clone(),
+                content: value.to_vec(),
+            });
+            Some(content_id)
+        }
+        _ => None,
+    });
+
+    match content_id {
+        Some(content_id) => element.with_child(
+            Element::node("Include")
+                .with_namespace(XOP_NS)
+                .with_attr("href", format!("cid:{}", content_id)),
+        ),
+        None => element.with_text(to_base64(value)),
+    }
+}
+
+/// Binary parts of a received message that `xop:Include` elements refer to, read by
+/// `binary_value` within `with_parts`.
+#[derive(Debug, Default)]
+pub struct Parts {
+    /// Content and number of references not read yet, by content id.
+    parts: BTreeMap<String, (usize, Vec<u8>)>,
+}
+
+impl Parts {
+    /// Content ids of the parts.
+    pub fn content_ids(&self) -> impl Iterator<Item = &str> {
+        self.parts.keys().map(|id| id.as_str())
+    }
+}
+
+thread_local! {
+    static PARTS: RefCell<Option<Parts>> = const { RefCell::new(None) };
+}
+
+/// Restores the enclosing parts, even if deserialization panics.
+struct RestoreParts(Option<Parts>);
+
+impl Drop for RestoreParts {
+    fn drop(&mut self) {
+        PARTS.with(|p| *p.borrow_mut() = self.0.take());
+    }
+}
+
+/// Take the parts that the `xop:Include` elements of a received envelope refer to out of
+/// the message, without copying them.
+pub fn resolve(element: &Element, multipart: &mut Multipart) -> Result<Parts, Error> {
+    let mut references = BTreeMap::new();
+    count_includes(element, &mut references);
+
+    let mut parts = BTreeMap::new();
+    for (content_id, count) in references {
+        let part = multipart
+            .parts
+            .iter_mut()
+            .find(|p| p.content_id() == Some(&content_id))
+            .ok_or_else(|| Error::MissingPart {
+                content_id: content_id.clone(),
+            })?;
+        parts.insert(content_id, (count, std::mem::take(&mut part.body)));
+    }
+    Ok(Parts { parts })
+}
+
+fn count_includes(element: &Element, references: &mut BTreeMap<String, usize>) {
+    for child in element.children.iter().filter_map(|c| c.as_element()) {
+        if child.matches("Include", Some(XOP_NS)) {
+            *references.entry(include_id(child)).or_default() += 1;
+        } else {
+            count_includes(child, references);
+        }
+    }
+}
+
+/// Content id an `xop:Include` refers to.
+fn include_id(include: &Element) -> String {
+    let href = include.attributes.get("href").map(|h| h.as_str());
+    let href = href.unwrap_or_default();
+    percent_decode(href.strip_prefix("cid:").unwrap_or(href))
+}
+
+/// Run `deserialize`, `binary_value` reading the attachments of `parts`.
+pub fn with_parts<T>(parts: Parts, deserialize: impl FnOnce() -> T) -> T {
+    let restore = RestoreParts(PARTS.with(|p| p.borrow_mut().replace(parts)));
+    let value = deserialize();
+    drop(restore);
+    value
+}
+
+/// Read an `xs:base64Binary` value from an element: the attachment its `xop:Include`
+/// child refers to, moved out of the parts of `with_parts`, or its base64 text.
+pub fn binary_value(element: &Element) -> Result<Vec<u8>, crate::Error> {
+    let include = element
+        .children
+        .iter()
+        .filter_map(|c| c.as_element())
+        .find(|e| e.matches("Include", Some(XOP_NS)));
+    let content_id = match include {
+        Some(include) => include_id(include),
+        None => {
+            let text = element.get_text().unwrap_or_default();
+            return Ok(from_base64(&element.name, &text)?);
+        }
+    };
+
+    let content = PARTS.with(|p| {
+        let mut p = p.borrow_mut();
+        let parts = &mut p.as_mut()?.parts;
+        match parts.get_mut(&content_id)? {
+            // only values referred to more than once are copied
+            (references, content) if *references > 1 => {
+                *references -= 1;
+                Some(content.clone())
+            }
+            _ => parts.remove(&content_id).map(|(_, content)| content),
+        }
+    });
+    content.ok_or_else(|| Error::MissingPart { content_id }.into())
+}
+
+/// Decode the `%xx` escapes of a `cid:` URL.
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = bytes
+            .get(i + 1..i + 3)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (bytes[i], escaped) {
+            (b'%', Some(b)) => {
+                decoded.push(b);
+                i += 3;
+            }
+            (b, _) => {
+                decoded.push(b);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::multipart::Part;
+
+    #[test]
+    fn optimizes_large_values() {
+        let (elements, attachments) = optimize(4, || {
+            vec![
+                binary_element(Element::node("small"), b"abc"),
+                binary_element(Element::node("large"), b"abcd"),
+            ]
+        });
+        assert_eq!(elements[0].get_text().unwrap(), "YWJj");
+        let include = elements[1].get_child("Include").unwrap();
+        assert_eq!(include.namespace.as_deref(), Some(XOP_NS));
+        assert_eq!(include.attributes["href"], "cid:0.attachment@savon");
+        assert_eq!(
+            attachments,
+            [Attachment {
+                content_id: "0.attachment@savon".to_string(),
+                content: b"abcd".to_vec(),
+            }]
+        );
+
+        // outside of `optimize`, values are always inlined
+        let element = binary_element(Element::node("large"), b"abcd");
+        assert_eq!(element.get_text().unwrap(), "YWJjZA==");
+    }
+
+    #[test]
+    fn resolves_includes() {
+        let xml = format!(
+            r#"<Body><doc><content><xop:Include xmlns:xop="{ns}" href="cid:a%40b"/></content><copy><xop:Include xmlns:xop="{ns}" href="cid:a@b"/></copy><inline>c2F2b24=</inline></doc></Body>"#,
+            ns = XOP_NS
+        );
+        let element = Element::parse(xml.as_bytes()).unwrap();
+        let mut multipart = Multipart {
+            start: None,
+            parts: vec![Part::new("application/octet-stream", b"savon".to_vec())
+                .with_header("Content-ID", "<a@b>")],
+        };
+
+        let parts = resolve(&element, &mut multipart).unwrap();
+        assert_eq!(parts.content_ids().collect::<Vec<_>>(), ["a@b"]);
+        // moved out of the message
+        assert!(multipart.parts[0].body.is_empty());
+
+        let doc = element.get_child("doc").unwrap();
+        let values = with_parts(parts, || {
+            ["content", "copy", "content", "inline"]
+                .iter()
+                .map(|name| binary_value(doc.get_child(*name).unwrap()).ok())
+                .collect::<Vec<_>>()
+        });
+        assert_eq!(
+            values,
+            [
+                Some(b"savon".to_vec()),
+                Some(b"savon".to_vec()),
+                None,
+                Some(b"savon".to_vec())
+            ]
+        );
+        // outside of `with_parts`, includes can't be read
+        assert!(binary_value(doc.get_child("content").unwrap()).is_err());
+
+        assert_eq!(
+            resolve(&element, &mut Multipart::default()).unwrap_err(),
+            Error::MissingPart {
+                content_id: "a@b".to_string()
+            }
+        );
+    }
+}
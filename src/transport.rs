@@ -0,0 +1,158 @@
+//! HTTP transports sending the requests of generated clients.
+//!
+//! `reqwest::Client` is the default transport, and `reqwest::blocking::Client` the one of
+//! blocking clients, with savon's `blocking` feature. Others, like hyper, a proxy or
+//! `MockTransport` for tests, implement `Transport` and are given to a client with
+//! `with_transport`.
+
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+use crate::auth::BoxFuture;
+
+/// HTTP request posting an envelope.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct HttpRequest {
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl HttpRequest {
+    /// Value of a header, looked up case insensitively.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        find_header(&self.headers, name)
+    }
+}
+
+/// HTTP response to a request.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct HttpResponse {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl HttpResponse {
+    /// `200 OK` response with an XML body.
+    pub fn xml(body: &str) -> Self {
+        HttpResponse {
+            status: 200,
+            headers: vec![(
+                "Content-Type".to_string(),
+                "text/xml; charset=utf-8".to_string(),
+            )],
+            body: body.as_bytes().to_vec(),
+        }
+    }
+
+    /// Value of a header, looked up case insensitively.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        find_header(&self.headers, name)
+    }
+}
+
+/// Sends HTTP requests.
+pub trait Transport: Send + Sync {
+    fn send(&self, request: HttpRequest) -> BoxFuture<'_, Result<HttpResponse, crate::Error>>;
+}
+
+impl Transport for reqwest::Client {
+    fn send(&self, request: HttpRequest) -> BoxFuture<'_, Result<HttpResponse, crate::Error>> {
+        Box::pin(async move {
+            let mut builder = self.post(&request.url);
+            for (name, value) in request.headers {
+                builder = builder.header(name, value);
+            }
+            let response = builder.body(request.body).send().await?;
+
+            let status = response.status().as_u16();
+            let headers = response
+                .headers()
+                .iter()
+                .filter_map(|(name, value)| {
+                    Some((name.to_string(), value.to_str().ok()?.to_string()))
+                })
+                .collect();
+            let body = response.bytes().await?.to_vec();
+            Ok(HttpResponse {
+                status,
+                headers,
+                body,
+            })
+        })
+    }
+}
+
+/// Sends the request as the future is first polled, blocking the thread: it is meant for
+/// blocking clients, and must not be used by async ones.
+#[cfg(feature = "blocking")]
+impl Transport for reqwest::blocking::Client {
+    fn send(&self, request: HttpRequest) -> BoxFuture<'_, Result<HttpResponse, crate::Error>> {
+        Box::pin(async move {
+            let mut builder = self.post(&request.url);
+            for (name, value) in request.headers {
+                builder = builder.header(name, value);
+            }
+            let response = builder.body(request.body).send()?;
+
+            let status = response.status().as_u16();
+            let headers = response
+                .headers()
+                .iter()
+                .filter_map(|(name, value)| {
+                    Some((name.to_string(), value.to_str().ok()?.to_string()))
+                })
+                .collect();
+            let body = response.bytes()?.to_vec();
+            Ok(HttpResponse {
+                status,
+                headers,
+                body,
+            })
+        })
+    }
+}
+
+/// In-memory transport for tests, answering with queued responses and recording requests.
+#[derive(Debug, Default)]
+pub struct MockTransport {
+    responses: Mutex<VecDeque<HttpResponse>>,
+    requests: Mutex<Vec<HttpRequest>>,
+}
+
+impl MockTransport {
+    pub fn new() -> Self {
+        MockTransport::default()
+    }
+
+    /// Queue the response to a future request.
+    pub fn push_response(&self, response: HttpResponse) {
+        self.responses.lock().unwrap().push_back(response);
+    }
+
+    /// Requests received so far.
+    pub fn requests(&self) -> Vec<HttpRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+impl Transport for MockTransport {
+    fn send(&self, request: HttpRequest) -> BoxFuture<'_, Result<HttpResponse, crate::Error>> {
+        self.requests.lock().unwrap().push(request);
+        let response = self
+            .responses
+            .lock()
+            .unwrap()
+            .pop_front()
+            .ok_or_else(|| crate::Error::Transport("no response queued".to_string()));
+        Box::pin(async move { response })
+    }
+}
+
+fn find_header<'a>(headers: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    headers
+        .iter()
+        .find(|(n, _)| n.eq_ignore_ascii_case(name))
+        .map(|(_, v)| v.as_str())
+}
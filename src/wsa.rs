@@ -0,0 +1,137 @@
+//! WS-Addressing headers: `wsa:Action`, `wsa:To`, `wsa:MessageID`, `wsa:ReplyTo` and
+//! `wsa:RelatesTo`.
+
+use xmltree::Element;
+
+use crate::rpser::xml::BuildElement;
+use crate::rpser::SOAP_ENVELOPE_NS;
+
+/// WS-Addressing 1.0 namespace.
+pub const WSA_NS: &str = "http://www.w3.org/2005/08/addressing";
+/// Submission namespace, still used by older services.
+pub const WSA_SUBMISSION_NS: &str = "http://schemas.xmlsoap.org/ws/2004/08/addressing";
+/// Address asking for the reply in the HTTP response.
+pub const ANONYMOUS: &str = "http://www.w3.org/2005/08/addressing/anonymous";
+
+/// Addressing headers of a request.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Addressing {
+    pub action: String,
+    pub to: String,
+    pub message_id: String,
+    pub reply_to: String,
+}
+
+impl Addressing {
+    /// Headers for a request to `to`, with a fresh message id and an anonymous reply address.
+    pub fn new(action: &str, to: &str) -> Self {
+        Addressing {
+            action: action.to_string(),
+            to: to.to_string(),
+            message_id: message_id(),
+            reply_to: ANONYMOUS.to_string(),
+        }
+    }
+
+    /// Build the header elements, `Action` and `To` being marked `soap:mustUnderstand`.
+    ///
+    /// The attribute is in the SOAP 1.1 envelope namespace, as savon sends SOAP 1.1
+    /// envelopes: these elements don't fit in a SOAP 1.2 envelope as is.
+    pub fn to_elements(&self) -> Vec<Element> {
+        let node = |name: &str| Element::node(name).with_namespace(WSA_NS);
+        vec![
+            node("Action")
+                .with_qualified_attr(SOAP_ENVELOPE_NS, "mustUnderstand", "1")
+                .with_text(self.action.clone()),
+            node("MessageID").with_text(self.message_id.clone()),
+            node("ReplyTo").with_child(node("Address").with_text(self.reply_to.clone())),
+            node("To")
+                .with_qualified_attr(SOAP_ENVELOPE_NS, "mustUnderstand", "1")
+                .with_text(self.to.clone()),
+        ]
+    }
+}
+
+/// Random `urn:uuid:` message id.
+pub fn message_id() -> String {
+    let mut bytes = [0u8; 16];
+    getrandom::getrandom(&mut bytes).expect("no random source for the WS-Addressing message id");
+    // version 4, variant 1
+    bytes[6] = (bytes[6] & 0x0f) | 0x40;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+    let hex = bytes
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect::<String>();
+    format!(
+        "urn:uuid:{}-{}-{}-{}-{}",
+        &hex[..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..]
+    )
+}
+
+/// Message id a response replies to, from its `wsa:RelatesTo` header.
+pub fn relates_to(headers: &[Element]) -> Option<String> {
+    headers
+        .iter()
+        .find(|h| {
+            h.matches("RelatesTo", Some(WSA_NS)) || h.matches("RelatesTo", Some(WSA_SUBMISSION_NS))
+        })
+        .and_then(|h| h.get_text())
+        .map(|text| text.trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builds_request_headers() {
+        let addressing = Addressing::new("urn:action", "http://localhost/service");
+        assert!(addressing.message_id.starts_with("urn:uuid:"));
+        assert_eq!(addressing.message_id.len(), 45);
+        assert_eq!(&addressing.message_id[23..24], "4");
+        assert_ne!(addressing.message_id, message_id());
+
+        let elements = addressing.to_elements();
+        let names = elements.iter().map(|e| e.name.as_str()).collect::<Vec<_>>();
+        assert_eq!(names, ["Action", "MessageID", "ReplyTo", "To"]);
+        assert!(elements
+            .iter()
+            .all(|e| e.namespace.as_deref() == Some(WSA_NS)));
+        assert_eq!(elements[0].get_text().unwrap(), "urn:action");
+        assert_eq!(
+            elements[0].attributes[&format!("{{{}}}mustUnderstand", SOAP_ENVELOPE_NS)],
+            "1"
+        );
+        assert_eq!(
+            elements[2]
+                .get_child("Address")
+                .unwrap()
+                .get_text()
+                .unwrap(),
+            ANONYMOUS
+        );
+        assert_eq!(elements[3].get_text().unwrap(), "http://localhost/service");
+    }
+
+    #[test]
+    fn reads_relates_to() {
+        let response = r#"<soap:Envelope xmlns:soap="http://schemas.xmlsoap.org/soap/envelope/" xmlns:a="http://www.w3.org/2005/08/addressing">
+            <soap:Header>
+                <a:Action>urn:action/Response</a:Action>
+                <a:RelatesTo> urn:uuid:0f8fad5b-d9cb-469f-a165-70867728950e </a:RelatesTo>
+            </soap:Header>
+            <soap:Body><Response/></soap:Body>
+        </soap:Envelope>"#;
+        let response = crate::rpser::Response::from_xml(response).unwrap();
+        assert_eq!(
+            relates_to(&response.headers).as_deref(),
+            Some("urn:uuid:0f8fad5b-d9cb-469f-a165-70867728950e")
+        );
+        assert_eq!(relates_to(&[]), None);
+    }
+}
@@ -0,0 +1,268 @@
+//! WS-Security headers: `UsernameToken` authentication, `wsu:Timestamp` and signatures.
+
+use std::fmt::{self, Debug};
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use chrono::{DateTime, Duration, SecondsFormat, Utc};
+use sha1::{Digest, Sha1};
+use xmltree::Element;
+
+use crate::dsig::{Signer, Verifier};
+use crate::rpser::xml::BuildElement;
+use crate::rpser::SOAP_ENVELOPE_NS;
+
+/// WS-Security extension namespace.
+pub const WSSE_NS: &str =
+    "http://docs.oasis-open.org/wss/2004/01/oasis-200401-wss-wssecurity-secext-1.0.xsd";
+/// WS-Security utility namespace.
+pub const WSU_NS: &str =
+    "http://docs.oasis-open.org/wss/2004/01/oasis-200401-wss-wssecurity-utility-1.0.xsd";
+
+const PASSWORD_TEXT: &str = "http://docs.oasis-open.org/wss/2004/01/oasis-200401-wss-username-token-profile-1.0#PasswordText";
+const PASSWORD_DIGEST: &str = "http://docs.oasis-open.org/wss/2004/01/oasis-200401-wss-username-token-profile-1.0#PasswordDigest";
+pub(crate) const BASE64_BINARY: &str = "http://docs.oasis-open.org/wss/2004/01/oasis-200401-wss-soap-message-security-1.0#Base64Binary";
+
+/// How the password of a `UsernameToken` is sent.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PasswordType {
+    /// In clear, only suitable over TLS.
+    Text,
+    /// As `Base64(SHA-1(nonce + created + password))`, with a fresh nonce.
+    Digest,
+}
+
+#[derive(Clone)]
+pub struct UsernameToken {
+    pub username: String,
+    pub password: String,
+    pub password_type: PasswordType,
+}
+
+impl Debug for UsernameToken {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // the password is kept out of logs
+        f.debug_struct("UsernameToken")
+            .field("username", &self.username)
+            .field("password_type", &self.password_type)
+            .finish_non_exhaustive()
+    }
+}
+
+/// `wsse:Security` header, added to every request of a client through
+/// `savon::http::Options::security`.
+#[derive(Clone, Debug)]
+pub struct Security {
+    pub username_token: Option<UsernameToken>,
+    /// Lifetime of the `wsu:Timestamp`, which is not sent when `None`.
+    pub timestamp: Option<Duration>,
+    /// Send `soap:mustUnderstand="1"`, so the server fails rather than ignoring the header.
+    pub must_understand: bool,
+    /// Sign the Body and the Timestamp of requests.
+    ///
+    /// Binary values are then sent inline rather than with MTOM, for the signature to cover them.
+    pub signer: Option<Signer>,
+    /// Reject responses without a valid signature.
+    pub verifier: Option<Verifier>,
+}
+
+impl Default for Security {
+    fn default() -> Self {
+        Security {
+            username_token: None,
+            timestamp: None,
+            must_understand: true,
+            signer: None,
+            verifier: None,
+        }
+    }
+}
+
+impl Security {
+    /// Authenticate with a username and password.
+    pub fn username_token(username: &str, password: &str, password_type: PasswordType) -> Self {
+        Security {
+            username_token: Some(UsernameToken {
+                username: username.to_string(),
+                password: password.to_string(),
+                password_type,
+            }),
+            ..Security::default()
+        }
+    }
+
+    /// Add a `wsu:Timestamp` expiring after `ttl`.
+    pub fn with_timestamp(mut self, ttl: Duration) -> Self {
+        self.timestamp = Some(ttl);
+        self
+    }
+
+    /// Sign requests with a private key and its certificate.
+    pub fn with_signer(mut self, signer: Signer) -> Self {
+        self.signer = Some(signer);
+        self
+    }
+
+    /// Verify the signature of responses against a trusted certificate.
+    pub fn with_verifier(mut self, verifier: Verifier) -> Self {
+        self.verifier = Some(verifier);
+        self
+    }
+
+    /// Build the header, with a fresh nonce and timestamps.
+    pub fn to_element(&self) -> Element {
+        let mut nonce = [0; 16];
+        getrandom::getrandom(&mut nonce).expect("no random source for the WS-Security nonce");
+        self.to_element_at(Utc::now(), &nonce)
+    }
+
+    fn to_element_at(&self, now: DateTime<Utc>, nonce: &[u8]) -> Element {
+        let created = timestamp(now);
+        let mut security = Element::node("Security").with_namespace(WSSE_NS);
+        if self.must_understand {
+            security = security.with_qualified_attr(SOAP_ENVELOPE_NS, "mustUnderstand", "1");
+        }
+
+        if let Some(ttl) = self.timestamp {
+            security = security.with_child(
+                Element::node("Timestamp")
+                    .with_namespace(WSU_NS)
+                    .with_children(vec![
+                        Element::node("Created")
+                            .with_namespace(WSU_NS)
+                            .with_text(created.clone()),
+                        Element::node("Expires")
+                            .with_namespace(WSU_NS)
+                            .with_text(timestamp(now + ttl)),
+                    ]),
+            );
+        }
+
+        if let Some(token) = self.username_token.as_ref() {
+            let username = Element::node("Username")
+                .with_namespace(WSSE_NS)
+                .with_text(token.username.clone());
+            let children = match token.password_type {
+                PasswordType::Text => vec![
+                    username,
+                    Element::node("Password")
+                        .with_namespace(WSSE_NS)
+                        .with_attr("Type", PASSWORD_TEXT)
+                        .with_text(token.password.clone()),
+                ],
+                PasswordType::Digest => vec![
+                    username,
+                    Element::node("Password")
+                        .with_namespace(WSSE_NS)
+                        .with_attr("Type", PASSWORD_DIGEST)
+                        .with_text(password_digest(nonce, &created, &token.password)),
+                    Element::node("Nonce")
+                        .with_namespace(WSSE_NS)
+                        .with_attr("EncodingType", BASE64_BINARY)
+                        .with_text(STANDARD.encode(nonce)),
+                    Element::node("Created")
+                        .with_namespace(WSU_NS)
+                        .with_text(created),
+                ],
+            };
+            security = security.with_child(
+                Element::node("UsernameToken")
+                    .with_namespace(WSSE_NS)
+                    .with_children(children),
+            );
+        }
+
+        security
+    }
+}
+
+/// `PasswordDigest` of the UsernameToken profile: `Base64(SHA-1(nonce + created + password))`.
+pub fn password_digest(nonce: &[u8], created: &str, password: &str) -> String {
+    let mut sha1 = Sha1::new();
+    sha1.update(nonce);
+    sha1.update(created.as_bytes());
+    sha1.update(password.as_bytes());
+    STANDARD.encode(sha1.finalize())
+}
+
+fn timestamp(time: DateTime<Utc>) -> String {
+    time.to_rfc3339_opts(SecondsFormat::Millis, true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn digests_passwords() {
+        assert_eq!(
+            password_digest(b"0123456789abcdef", "2024-01-02T03:04:05.000Z", "secret"),
+            "nEbrbKfZiWZ5g4X5zHpUi6b1fR8="
+        );
+    }
+
+    #[test]
+    fn builds_username_token_with_digest() {
+        let now = Utc.with_ymd_and_hms(2024, 1, 2, 3, 4, 5).unwrap();
+        let security = Security::username_token("alice", "secret", PasswordType::Digest)
+            .with_timestamp(Duration::minutes(5));
+        let element = security.to_element_at(now, b"0123456789abcdef");
+
+        assert!(element.matches("Security", Some(WSSE_NS)));
+        assert_eq!(
+            element.attributes[&format!("{{{}}}mustUnderstand", SOAP_ENVELOPE_NS)],
+            "1"
+        );
+        let timestamp = element.find_child("Timestamp", Some(WSU_NS)).unwrap();
+        let times = timestamp
+            .children
+            .iter()
+            .filter_map(|c| c.as_element())
+            .map(|e| e.get_text().unwrap().into_owned())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            times,
+            ["2024-01-02T03:04:05.000Z", "2024-01-02T03:09:05.000Z"]
+        );
+
+        let token = element.find_child("UsernameToken", Some(WSSE_NS)).unwrap();
+        let child = |name, ns| token.find_child(name, Some(ns)).unwrap();
+        assert_eq!(child("Username", WSSE_NS).get_text().unwrap(), "alice");
+        let password = child("Password", WSSE_NS);
+        assert_eq!(password.attributes["Type"], PASSWORD_DIGEST);
+        assert_eq!(password.get_text().unwrap(), "nEbrbKfZiWZ5g4X5zHpUi6b1fR8=");
+        assert_eq!(
+            child("Nonce", WSSE_NS).get_text().unwrap(),
+            "MDEyMzQ1Njc4OWFiY2RlZg=="
+        );
+        assert_eq!(
+            child("Created", WSU_NS).get_text().unwrap(),
+            "2024-01-02T03:04:05.000Z"
+        );
+    }
+
+    #[test]
+    fn builds_username_token_with_text() {
+        let security = Security {
+            must_understand: false,
+            ..Security::username_token("alice", "secret", PasswordType::Text)
+        };
+        let element = security.to_element();
+
+        assert!(element.attributes.is_empty());
+        assert!(element.find_child("Timestamp", Some(WSU_NS)).is_none());
+        let token = element.find_child("UsernameToken", Some(WSSE_NS)).unwrap();
+        let password = token.find_child("Password", Some(WSSE_NS)).unwrap();
+        assert_eq!(password.attributes["Type"], PASSWORD_TEXT);
+        assert_eq!(password.get_text().unwrap(), "secret");
+        assert!(token.find_child("Nonce", Some(WSSE_NS)).is_none());
+
+        let options = crate::http::Options {
+            security: Some(security),
+            ..Default::default()
+        };
+        let debug = format!("{:?}", options);
+        assert!(debug.contains("alice") && debug.contains("Text") && !debug.contains("secret"));
+    }
+}